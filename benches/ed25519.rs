@@ -0,0 +1,161 @@
+//! Measures the NEAR gas of the `nearEd25519Verify` precompile, to price it.
+//!
+//! The precompile runs in-wasm, so it is priced at the NEAR gas per EVM gas
+//! of `ecrecover`, which does the most similar work. Deploying init code that
+//! calls a precompile many times, and the same code calling `identity` in its
+//! place, gives the NEAR gas of a single call. The EVM gas this implies is
+//! compared with the costs in `src/precompiles/ed25519.rs`.
+//!
+//! Needs a `release.wasm` with the precompile, built with
+//! `make release ed25519=yes`.
+
+use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+
+use aurora_engine::parameters::NewCallArgs;
+
+near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
+    EVM_WASM_BYTES => "release.wasm"
+}
+
+const CONTRACT_ID: &str = "aurora";
+
+/// Number of calls per measurement.
+const ITERATIONS: u64 = 50;
+
+/// The EVM gas of an `ecrecover` call.
+const ECRECOVER_GAS: u64 = 3_000;
+
+/// The costs of `nearEd25519Verify` the engine charges.
+const ED25519_VERIFY_BASE: u64 = 4_500;
+const ED25519_VERIFY_PER_WORD: u64 = 15;
+
+/// Words of the longer signed message.
+const MESSAGE_WORDS: u64 = 32;
+
+const IDENTITY: [u8; 20] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4];
+const ECRECOVER: [u8; 20] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+const ED25519_VERIFY: [u8; 20] = [
+    0xd4, 0x38, 0x26, 0xdb, 0xa7, 0xd1, 0xfb, 0xf4, 0x0c, 0x43, 0xd0, 0x2a, 0xbb, 0x6a, 0xbc, 0x5c,
+    0xbf, 0xac, 0xf2, 0x1d,
+];
+
+/// A valid `ecrecover` input, `ValidKey` of the test vectors.
+const ECRECOVER_INPUT: &str = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+    000000000000000000000000000000000000000000000000000000000000001b\
+    38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+    789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02";
+
+fn deploy_evm() -> UserAccount {
+    let master_account = near_sdk_sim::init_simulator(None);
+    let contract_account =
+        master_account.deploy(*EVM_WASM_BYTES, CONTRACT_ID.to_string(), to_yocto("1000"));
+    let args = NewCallArgs {
+        chain_id: [0u8; 32],
+        owner_id: master_account.account_id.clone(),
+        bridge_prover_id: CONTRACT_ID.to_string(),
+        upgrade_delay_blocks: 1,
+    };
+    contract_account
+        .call(
+            CONTRACT_ID.to_string(),
+            "new",
+            &args.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            STORAGE_AMOUNT,
+        )
+        .assert_success();
+    master_account
+}
+
+/// A valid `nearEd25519Verify` input, for a message of `len` bytes.
+fn ed25519_input(len: usize) -> Vec<u8> {
+    let message = vec![0x42; len];
+    let signer = InMemorySigner::from_seed("test.near", KeyType::ED25519, "test");
+    let public_key = match signer.public_key.clone() {
+        PublicKey::ED25519(public_key) => public_key.0,
+        PublicKey::SECP256K1(_) => unreachable!(),
+    };
+    let signature = match signer.sign(&message) {
+        Signature::ED25519(signature) => signature.to_bytes(),
+        Signature::SECP256K1(_) => unreachable!(),
+    };
+
+    let mut input = Vec::new();
+    input.extend_from_slice(&public_key);
+    input.extend_from_slice(&signature);
+    input.extend_from_slice(&message);
+    input
+}
+
+/// NEAR gas burnt deploying init code that copies `input` to memory and
+/// passes it to `STATICCALL`s of `address` `ITERATIONS` times, deploying
+/// nothing.
+fn gas_burnt(account: &UserAccount, address: [u8; 20], input: &[u8]) -> u64 {
+    let len = (input.len() as u16).to_be_bytes();
+    let mut call = vec![
+        0x60, 0x00, 0x60, 0x00, 0x61, len[0], len[1], 0x60, 0x00, 0x73,
+    ];
+    call.extend_from_slice(&address);
+    call.extend_from_slice(&[0x5a, 0xfa, 0x50]);
+
+    let offset = (9 + call.len() as u64 * ITERATIONS + 1) as u16;
+    let offset = offset.to_be_bytes();
+    let mut code = vec![
+        0x61, len[0], len[1], 0x61, offset[0], offset[1], 0x60, 0x00, 0x39,
+    ];
+    for _ in 0..ITERATIONS {
+        code.extend_from_slice(&call);
+    }
+    code.push(0x00);
+    code.extend_from_slice(input);
+
+    let result = account.call(
+        CONTRACT_ID.to_string(),
+        "deploy_code",
+        &code,
+        DEFAULT_GAS,
+        0,
+    );
+    result.assert_success();
+    result.gas_burnt()
+}
+
+/// NEAR gas of a single call of `address` with `input`.
+fn near_gas(account: &UserAccount, address: [u8; 20], input: &[u8]) -> u64 {
+    let baseline = gas_burnt(account, IDENTITY, input);
+    gas_burnt(account, address, input).saturating_sub(baseline) / ITERATIONS
+}
+
+fn main() {
+    let account = deploy_evm();
+    // Warm up, so all measurements see the same storage.
+    gas_burnt(&account, IDENTITY, &[]);
+
+    let ecrecover = near_gas(&account, ECRECOVER, &hex::decode(ECRECOVER_INPUT).unwrap());
+    let near_gas_per_evm_gas = ecrecover / ECRECOVER_GAS;
+    let empty = near_gas(&account, ED25519_VERIFY, &ed25519_input(0));
+    let long = near_gas(
+        &account,
+        ED25519_VERIFY,
+        &ed25519_input(32 * MESSAGE_WORDS as usize),
+    );
+    let per_word = long.saturating_sub(empty) / MESSAGE_WORDS;
+
+    println!("ecrecover NEAR gas   {:>15}", ecrecover);
+    println!("NEAR gas per EVM gas {:>15}", near_gas_per_evm_gas);
+    println!("nearEd25519Verify:");
+    println!("  NEAR gas, empty message  {:>15}", empty);
+    println!("  NEAR gas per word        {:>15}", per_word);
+    println!(
+        "  base EVM gas      {:>8} (charged {})",
+        empty / near_gas_per_evm_gas,
+        ED25519_VERIFY_BASE
+    );
+    println!(
+        "  per word EVM gas  {:>8} (charged {})",
+        per_word / near_gas_per_evm_gas,
+        ED25519_VERIFY_PER_WORD
+    );
+}
@@ -0,0 +1,88 @@
+//! Measures the NEAR gas saved by hashing with the `keccak256` host function
+//! instead of in-wasm.
+//!
+//! The engine itself routes its hashing through the host function, but the
+//! `SHA3` opcode still hashes in-wasm inside the `evm` crate. Deploying init
+//! code that runs `SHA3` many times, and the same code with `ADD` in its
+//! place, gives the in-wasm cost of a single hash, which is compared with the
+//! host function fees.
+//!
+//! Needs `release.wasm`, like the integration tests.
+
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+
+use aurora_engine::parameters::NewCallArgs;
+
+near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
+    EVM_WASM_BYTES => "release.wasm"
+}
+
+const CONTRACT_ID: &str = "aurora";
+
+/// NEAR fees of the `keccak256` host function, from the runtime config.
+const KECCAK256_BASE: u64 = 5_879_491_275;
+const KECCAK256_BYTE: u64 = 21_471_105;
+
+/// Number of hashes per measurement.
+const ITERATIONS: u64 = 200;
+
+/// Size of the hashed input, an ecrecover public key.
+const INPUT_LEN: u64 = 64;
+
+fn deploy_evm() -> UserAccount {
+    let master_account = near_sdk_sim::init_simulator(None);
+    let contract_account =
+        master_account.deploy(*EVM_WASM_BYTES, CONTRACT_ID.to_string(), to_yocto("1000"));
+    let args = NewCallArgs {
+        chain_id: [0u8; 32],
+        owner_id: master_account.account_id.clone(),
+        bridge_prover_id: CONTRACT_ID.to_string(),
+        upgrade_delay_blocks: 1,
+    };
+    contract_account
+        .call(
+            CONTRACT_ID.to_string(),
+            "new",
+            &args.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            STORAGE_AMOUNT,
+        )
+        .assert_success();
+    master_account
+}
+
+/// NEAR gas burnt deploying init code that runs `PUSH1 64 PUSH1 0 <opcode>
+/// POP` `ITERATIONS` times and deploys nothing.
+fn gas_burnt(account: &UserAccount, opcode: u8) -> u64 {
+    let mut code = Vec::new();
+    for _ in 0..ITERATIONS {
+        code.extend_from_slice(&[0x60, INPUT_LEN as u8, 0x60, 0x00, opcode, 0x50]);
+    }
+    code.push(0x00);
+    let result = account.call(
+        CONTRACT_ID.to_string(),
+        "deploy_code",
+        &code,
+        DEFAULT_GAS,
+        0,
+    );
+    result.assert_success();
+    result.gas_burnt()
+}
+
+fn main() {
+    let account = deploy_evm();
+    // Warm up, so both measurements see the same storage.
+    gas_burnt(&account, 0x01);
+
+    let sha3 = gas_burnt(&account, 0x20);
+    let add = gas_burnt(&account, 0x01);
+    let in_wasm = sha3.saturating_sub(add) / ITERATIONS;
+    let host = KECCAK256_BASE + INPUT_LEN * KECCAK256_BYTE;
+
+    println!("keccak256 of {} bytes, NEAR gas per hash:", INPUT_LEN);
+    println!("  in-wasm        {:>15}", in_wasm);
+    println!("  host function  {:>15}", host);
+    println!("  saved          {:>15}", in_wasm as i128 - host as i128);
+}
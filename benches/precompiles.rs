@@ -0,0 +1,129 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use evm::Context;
+use std::time::{Duration, Instant};
+
+use aurora_engine::precompiles::istanbul_precompiles;
+use aurora_engine::prelude::Address;
+
+const ECRECOVER: &str = "\
+    47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad\
+    000000000000000000000000000000000000000000000000000000000000001b\
+    650acf9d3f5f0a2c799776a1254355d5f4061762a237396a99a0e0e3fc2bcd67\
+    29514a0dacb2e623ac4abd157cb18163ff942280db4d5caad66ddf941ba12e03";
+
+const MODEXP: &str = "\
+    0000000000000000000000000000000000000000000000000000000000000001\
+    0000000000000000000000000000000000000000000000000000000000000020\
+    0000000000000000000000000000000000000000000000000000000000000020\
+    03\
+    fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+    fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+
+const BN128_ADD: &str = "\
+    18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+    063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+    07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+    06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7";
+
+const BN128_MUL: &str = "\
+    2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7\
+    21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204\
+    00000000000000000000000000000000000000000000000011138ce750fa15c2";
+
+const BN128_PAIR: &str = "\
+    1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
+    3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
+    209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
+    04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
+    2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
+    120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
+    111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
+    2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
+    198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+    1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+    090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+    12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+const BLAKE2F: &str = "\
+    0000000c\
+    48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+    d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+    6162630000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0000000000000000000000000000000000000000000000000000000000000000\
+    0300000000000000\
+    0000000000000000\
+    01";
+
+fn new_context() -> Context {
+    Context {
+        address: Default::default(),
+        caller: Default::default(),
+        apparent_value: Default::default(),
+    }
+}
+
+fn bench_precompile(c: &mut Criterion, name: &str, address: u64, input: &[u8]) {
+    let address = Address::from_low_u64_be(address);
+    let context = new_context();
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            istanbul_precompiles(
+                black_box(address),
+                black_box(input),
+                Some(u64::MAX),
+                &context,
+            )
+            .unwrap()
+            .unwrap()
+        })
+    });
+}
+
+/// Most a precompile check of an address that is not a precompile may take
+/// on average. A table lookup takes a few nanoseconds; building a map or
+/// reading storage on every call is well above this.
+const DISPATCH_BUDGET: Duration = Duration::from_nanos(50);
+
+/// The cost every call to a regular contract pays for the precompile check.
+fn bench_dispatch(c: &mut Criterion) {
+    let address = Address::repeat_byte(0x42);
+    let context = new_context();
+    c.bench_function("dispatch (not a precompile)", |b| {
+        b.iter(|| istanbul_precompiles(black_box(address), &[], Some(u64::MAX), &context).is_none())
+    });
+
+    const ITERATIONS: u32 = 1_000_000;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(
+            istanbul_precompiles(black_box(address), &[], Some(u64::MAX), &context).is_none(),
+        );
+    }
+    let average = start.elapsed() / ITERATIONS;
+    assert!(
+        average < DISPATCH_BUDGET,
+        "precompile dispatch takes {:?} per call, over the {:?} budget",
+        average,
+        DISPATCH_BUDGET
+    );
+}
+
+fn precompiles(c: &mut Criterion) {
+    let hashing_input = vec![0xab; 1024];
+
+    bench_precompile(c, "ecrecover", 1, &hex::decode(ECRECOVER).unwrap());
+    bench_precompile(c, "sha256 (1 KiB)", 2, &hashing_input);
+    bench_precompile(c, "ripemd160 (1 KiB)", 3, &hashing_input);
+    bench_precompile(c, "identity (1 KiB)", 4, &hashing_input);
+    bench_precompile(c, "modexp", 5, &hex::decode(MODEXP).unwrap());
+    bench_precompile(c, "alt_bn128_add", 6, &hex::decode(BN128_ADD).unwrap());
+    bench_precompile(c, "alt_bn128_mul", 7, &hex::decode(BN128_MUL).unwrap());
+    bench_precompile(c, "alt_bn128_pair", 8, &hex::decode(BN128_PAIR).unwrap());
+    bench_precompile(c, "blake2f (12 rounds)", 9, &hex::decode(BLAKE2F).unwrap());
+    bench_dispatch(c);
+}
+
+criterion_group!(benches, precompiles);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rlp::{Decodable, Rlp};
+
+use aurora_engine::transaction::EthSignedTransaction;
+
+/// A plain transfer signed with an EIP-155 chain ID.
+const TRANSFER_TX: &str = "\
+    f86a8086d55698372431831e848094f0109fc8df283027b6285cc889f5aa624e\
+    ac1f55843b9aca008025a009ebb6ca057a0535d6186462bc0b465b561c94a295\
+    bdb0621fc19208ab149a9ca0440ffd775ce91a833ab410777204d5341a6f9fa9\
+    1216a6f3ee2c051fea6a0428";
+
+/// A contract deployment without a chain ID.
+const DEPLOY_TX: &str = "\
+    f901f680883362396163613030836691b78080b901a060806040526000805460\
+    01600160a01b0319163317905534801561002257600080fd5b5061016e806100\
+    326000396000f3fe608060405234801561001057600080fd5b50600436106100\
+    415760003560e01c8063445df0ac146100465780638da5cb5b14610060578063\
+    fdacd57614610084575b600080fd5b61004e6100a3565b604080519182525190\
+    81900360200190f35b6100686100a9565b604080516001600160a01b03909216\
+    8252519081900360200190f35b6100a16004803603602081101561009a576000\
+    80fd5b50356100b8565b005b60015481565b6000546001600160a01b03168156\
+    5b6000546001600160a01b031633146101015760405162461bcd60e51b815260\
+    0401808060200182810382526033815260200180610107603391396040019150\
+    5060405180910390fd5b60015556fe546869732066756e6374696f6e20697320\
+    7265737472696374656420746f2074686520636f6e74726163742773206f776e\
+    6572a265627a7a72315820b7e3396b30da5009ea603d5c2bdfd68577b979d581\
+    7fbe4fbd7d983f5c04ff3464736f6c634300050f00321ca0f0133510c01bc64a\
+    64f84b411082ff74bbc4a3aa5c720d2b5f61ad76716ee232a03412d91486eb01\
+    2423492af258a4cd3b03ce67dde7fdc93bbea142bce6a59c9f";
+
+fn decode(encoded: &[u8]) -> EthSignedTransaction {
+    EthSignedTransaction::decode(&Rlp::new(encoded)).unwrap()
+}
+
+fn transactions(c: &mut Criterion) {
+    for (name, encoded) in &[("transfer", TRANSFER_TX), ("deploy", DEPLOY_TX)] {
+        let encoded = hex::decode(encoded).unwrap();
+        c.bench_function(&format!("rlp decode ({})", name), |b| {
+            b.iter(|| decode(black_box(&encoded)))
+        });
+
+        let tx = decode(&encoded);
+        c.bench_function(&format!("sender recovery ({})", name), |b| {
+            b.iter(|| black_box(&tx).sender().unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, transactions);
+criterion_main!(benches);
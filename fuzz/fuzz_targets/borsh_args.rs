@@ -0,0 +1,15 @@
+#![no_main]
+
+use aurora_engine::parameters::{
+    FunctionCallArgs, GetStorageAtArgs, MetaCallArgs, NewCallArgs, ViewCallArgs,
+};
+use borsh::BorshDeserialize;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = NewCallArgs::try_from_slice(data);
+    let _ = MetaCallArgs::try_from_slice(data);
+    let _ = FunctionCallArgs::try_from_slice(data);
+    let _ = ViewCallArgs::try_from_slice(data);
+    let _ = GetStorageAtArgs::try_from_slice(data);
+});
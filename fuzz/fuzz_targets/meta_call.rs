@@ -0,0 +1,10 @@
+#![no_main]
+
+use aurora_engine::meta_parsing::{near_erc712_domain, parse_meta_call};
+use aurora_engine::prelude::U256;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let domain_separator = near_erc712_domain(U256::from(1313161555));
+    let _ = parse_meta_call(&domain_separator, b"evm", data.to_vec());
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use aurora_engine::transaction::EthSignedTransaction;
+use libfuzzer_sys::fuzz_target;
+use rlp::{Decodable, Rlp};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tx) = EthSignedTransaction::decode(&Rlp::new(data)) {
+        let _ = tx.chain_id();
+        let _ = tx.sender();
+        // Anything we accept must survive a round trip unchanged.
+        let encoded = rlp::encode(&tx);
+        let decoded = EthSignedTransaction::decode(&Rlp::new(&encoded)).unwrap();
+        assert_eq!(tx, decoded);
+    }
+});
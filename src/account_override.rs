@@ -0,0 +1,74 @@
+//! Timelocked overrides of account fields, for incident recovery.
+//!
+//! The owner stages an override of the balance and/or nonce of one address
+//! together with the hash of a published justification. It can only be
+//! applied `upgrade_delay_blocks` after staging, like an upgrade, and every
+//! step is logged, so the fix is visible on chain before it takes effect.
+//! Only one override can be staged at a time.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::engine::Engine;
+use crate::parameters::AccountOverrideArgs;
+use crate::prelude::{Address, String, U256};
+use crate::sdk;
+use crate::types::bytes_to_hex;
+
+const STAGED_OVERRIDE_KEY: &[u8; 24] = b"\0STAGED_ACCOUNT_OVERRIDE";
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct StagedOverride {
+    args: AccountOverrideArgs,
+    /// Block the override was staged in.
+    block: u64,
+}
+
+fn log_event(event: &str, args: &AccountOverrideArgs) {
+    let mut message = String::from(event);
+    message.push(':');
+    message.push_str(&bytes_to_hex(&args.try_to_vec().expect("ERR_SER")));
+    sdk::log(message);
+}
+
+fn get_staged() -> Option<StagedOverride> {
+    sdk::read_storage(STAGED_OVERRIDE_KEY)
+        .map(|bytes| StagedOverride::try_from_slice(&bytes).expect("ERR_DESER"))
+}
+
+pub fn get_staged_args() -> Option<AccountOverrideArgs> {
+    get_staged().map(|staged| staged.args)
+}
+
+pub fn stage(args: AccountOverrideArgs) {
+    if get_staged().is_some() {
+        sdk::panic_utf8(b"ERR_OVERRIDE_ALREADY_STAGED");
+    }
+    log_event("ACCOUNT_OVERRIDE_STAGED", &args);
+    let staged = StagedOverride {
+        args,
+        block: sdk::block_index(),
+    };
+    sdk::write_storage(STAGED_OVERRIDE_KEY, &staged.try_to_vec().expect("ERR_SER"));
+}
+
+pub fn cancel() {
+    let staged = get_staged().expect("ERR_NO_OVERRIDE");
+    log_event("ACCOUNT_OVERRIDE_CANCELLED", &staged.args);
+    sdk::remove_storage(STAGED_OVERRIDE_KEY);
+}
+
+pub fn apply(delay_blocks: u64) {
+    let staged = get_staged().expect("ERR_NO_OVERRIDE");
+    if sdk::block_index() <= staged.block + delay_blocks {
+        sdk::panic_utf8(b"ERR_NOT_ALLOWED:TOO_EARLY");
+    }
+    let address = Address(staged.args.address);
+    if let Some(balance) = &staged.args.balance {
+        Engine::set_balance(&address, &U256::from_big_endian(balance));
+    }
+    if let Some(nonce) = &staged.args.nonce {
+        Engine::set_nonce(&address, &U256::from_big_endian(nonce));
+    }
+    log_event("ACCOUNT_OVERRIDE_APPLIED", &staged.args);
+    sdk::remove_storage(STAGED_OVERRIDE_KEY);
+}
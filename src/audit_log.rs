@@ -0,0 +1,45 @@
+//! Append-only log of administrative actions.
+//!
+//! Every call of an administrative method that passes its access check is
+//! recorded with the block, the caller, and the hash of its input, so that
+//! how a deployment is governed can be followed on chain.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::parameters::AuditLogEntry;
+use crate::prelude::{String, Vec};
+use crate::sdk;
+
+const AUDIT_LOG_LEN_KEY: &[u8; 14] = b"\0AUDIT_LOG_LEN";
+const AUDIT_LOG_PREFIX: &[u8; 11] = b"\0AUDIT_LOG:";
+
+fn entry_key(index: u64) -> Vec<u8> {
+    [&AUDIT_LOG_PREFIX[..], &index.to_be_bytes()].concat()
+}
+
+pub fn len() -> u64 {
+    sdk::read_u64(AUDIT_LOG_LEN_KEY).unwrap_or(0)
+}
+
+pub fn record(method: &str, caller: Vec<u8>, input: &[u8]) {
+    let entry = AuditLogEntry {
+        block_height: sdk::block_index(),
+        caller: String::from_utf8(caller).expect("ERR_ACCOUNT_ID"),
+        method: String::from(method),
+        input_hash: sdk::keccak(input).0,
+    };
+    let index = len();
+    sdk::write_storage(&entry_key(index), &entry.try_to_vec().expect("ERR_SER"));
+    sdk::write_storage(AUDIT_LOG_LEN_KEY, &(index + 1).to_le_bytes());
+}
+
+/// Returns up to `limit` entries, starting with entry `from`.
+pub fn entries(from: u64, limit: u64) -> Vec<AuditLogEntry> {
+    let end = len().min(from.saturating_add(limit));
+    (from..end)
+        .map(|index| {
+            let bytes = sdk::read_storage(&entry_key(index)).expect("ERR_AUDIT_LOG");
+            AuditLogEntry::try_from_slice(&bytes).expect("ERR_DESER")
+        })
+        .collect()
+}
@@ -0,0 +1,70 @@
+//! Bump allocator for the contract.
+//!
+//! Every NEAR function call runs in a fresh wasm instance, so memory never has
+//! to be handed back: `alloc` only moves a pointer forward, growing the wasm
+//! memory when it runs out, and `dealloc` does nothing. This trades peak memory
+//! for much cheaper allocations than `wee_alloc`.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::wasm32;
+use core::cell::UnsafeCell;
+use core::ptr;
+
+const PAGE_SIZE: usize = 64 * 1024;
+
+pub struct BumpAlloc {
+    /// Next free address.
+    next: UnsafeCell<usize>,
+    /// End of the memory region the allocator currently bumps through.
+    end: UnsafeCell<usize>,
+}
+
+// The contract runs single-threaded.
+unsafe impl Sync for BumpAlloc {}
+
+impl BumpAlloc {
+    pub const INIT: Self = Self {
+        next: UnsafeCell::new(0),
+        end: UnsafeCell::new(0),
+    };
+}
+
+/// Allocates from `*next` if the allocation fits before `end`.
+fn bump(next: &mut usize, end: usize, layout: Layout) -> Option<*mut u8> {
+    let start = next.checked_add(layout.align() - 1)? & !(layout.align() - 1);
+    let new_next = start.checked_add(layout.size())?;
+    if new_next > end {
+        return None;
+    }
+    *next = new_next;
+    Some(start as *mut u8)
+}
+
+unsafe impl GlobalAlloc for BumpAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let next = &mut *self.next.get();
+        let end = &mut *self.end.get();
+        if let Some(ptr) = bump(next, *end, layout) {
+            return ptr;
+        }
+
+        // Grow by enough pages for the allocation to fit in the new pages alone.
+        let pages = match layout.size().checked_add(layout.align() + PAGE_SIZE - 1) {
+            Some(bytes) => bytes / PAGE_SIZE,
+            None => return ptr::null_mut(),
+        };
+        let previous_pages = wasm32::memory_grow(0, pages);
+        if previous_pages == usize::MAX {
+            return ptr::null_mut();
+        }
+        let region = previous_pages * PAGE_SIZE;
+        if region != *end {
+            // The new pages do not extend the current region, start over in them.
+            *next = region;
+        }
+        *end = region + pages * PAGE_SIZE;
+        bump(next, *end, layout).unwrap_or(ptr::null_mut())
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
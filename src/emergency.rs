@@ -0,0 +1,95 @@
+//! Pausing of the mutative methods and of the Aurora-specific precompiles.
+//!
+//! Besides the owner, an emergency account set by the owner can pause
+//! methods and precompiles. That is all the emergency account can do, so its
+//! key can be kept at hand for incident response without being able to
+//! upgrade the code or move funds. Only the owner can resume them.
+
+use crate::prelude::{String, ToString, TryInto, Vec};
+use crate::sdk;
+
+const EMERGENCY_ID_KEY: &[u8; 13] = b"\0EMERGENCY_ID";
+const PAUSED_PREFIX: &[u8; 8] = b"\0PAUSED:";
+/// Key of the mask of paused precompiles, see the `*_FLAG` constants in
+/// `precompiles`.
+const PAUSED_PRECOMPILES_KEY: &[u8; 19] = b"\0PAUSED_PRECOMPILES";
+
+/// Methods that can be paused.
+pub const PAUSABLE_METHODS: &[&str] = &["deploy_code", "call", "raw_call", "meta_call"];
+
+fn paused_key(method: &str) -> Vec<u8> {
+    [&PAUSED_PREFIX[..], method.as_bytes()].concat()
+}
+
+pub fn get_emergency_id() -> Vec<u8> {
+    sdk::read_storage(EMERGENCY_ID_KEY).unwrap_or_default()
+}
+
+pub fn set_emergency_id(account_id: &[u8]) {
+    if account_id.is_empty() {
+        sdk::remove_storage(EMERGENCY_ID_KEY);
+    } else {
+        sdk::write_storage(EMERGENCY_ID_KEY, account_id);
+    }
+}
+
+pub fn is_emergency_account(account_id: &[u8]) -> bool {
+    !account_id.is_empty() && get_emergency_id() == account_id
+}
+
+fn check_pausable(method: &str) {
+    if !PAUSABLE_METHODS.contains(&method) {
+        sdk::panic_utf8(b"ERR_NOT_PAUSABLE");
+    }
+}
+
+pub fn pause(method: &str) {
+    check_pausable(method);
+    sdk::write_storage(&paused_key(method), &[]);
+}
+
+pub fn resume(method: &str) {
+    check_pausable(method);
+    sdk::remove_storage(&paused_key(method));
+}
+
+pub fn is_paused(method: &str) -> bool {
+    sdk::storage_has_key(&paused_key(method))
+}
+
+pub fn paused_methods() -> Vec<String> {
+    PAUSABLE_METHODS
+        .iter()
+        .filter(|method| is_paused(method))
+        .map(|method| method.to_string())
+        .collect()
+}
+
+/// Fails the call if `method` is paused.
+pub fn require_running(method: &str) {
+    if is_paused(method) {
+        sdk::panic_utf8(b"ERR_PAUSED");
+    }
+}
+
+pub fn paused_precompiles() -> u32 {
+    match sdk::read_storage(PAUSED_PRECOMPILES_KEY) {
+        None => 0,
+        Some(bytes) => u32::from_le_bytes(bytes.as_slice().try_into().expect("ERR_DESER")),
+    }
+}
+
+fn set_paused_precompiles(mask: u32) {
+    sdk::write_storage(PAUSED_PRECOMPILES_KEY, &mask.to_le_bytes());
+}
+
+/// Pauses the precompiles whose flags are set in `mask`, in addition to the
+/// ones already paused.
+pub fn pause_precompiles(mask: u32) {
+    set_paused_precompiles(paused_precompiles() | mask);
+}
+
+/// Resumes the precompiles whose flags are set in `mask`.
+pub fn resume_precompiles(mask: u32) {
+    set_paused_precompiles(paused_precompiles() & !mask);
+}
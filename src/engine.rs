@@ -1,14 +1,22 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use core::cell::{Cell, RefCell};
 use evm::backend::{Apply, ApplyBackend, Backend, Basic, Log};
 use evm::executor::{MemoryStackState, StackExecutor, StackSubstateMetadata};
-use evm::{Config, CreateScheme, ExitError, ExitReason, ExitSucceed};
+use evm::{Config, Context, CreateScheme, ExitError, ExitReason, ExitSucceed};
 
-use crate::parameters::{FunctionCallArgs, NewCallArgs, ViewCallArgs};
-use crate::precompiles;
-use crate::prelude::{Address, Borrowed, Vec, H256, U256};
+use crate::parameters::{
+    BlockCheckpoint, FunctionCallArgs, GasPriceBounds, NewCallArgs, StorageKeyKind, ViewCallArgs,
+};
+use crate::precompiles::{EvmPrecompileResult, PrecompileSet};
+use crate::prelude::{Address, Borrowed, HashMap, Vec, H256, U256};
 use crate::sdk;
-use crate::storage::{address_to_key, storage_to_key, KeyPrefix};
-use crate::types::{bytes_to_hex, log_to_bytes, u256_to_arr, AccountId, NonceError};
+use crate::storage::{
+    address_to_key, block_to_key, code_chunk_key, hash_to_key, pad_value, parse_key, trim_value,
+    KeyPrefix, StorageKeys,
+};
+use crate::types::{
+    keccak, log_to_hex, u256_to_arr, AccountId, LimitError, NonceError, RawAddress,
+};
 
 /// Engine internal state, mostly configuration.
 /// Should not contain anything large or enumerable.
@@ -40,6 +48,19 @@ impl From<NewCallArgs> for EngineState {
 pub struct Engine {
     state: EngineState,
     origin: Address,
+    /// Contract code already read from storage during this call, so that
+    /// repeated calls into the same contract only read it once.
+    code_cache: RefCell<HashMap<Address, Vec<u8>>>,
+    /// Storage slots already read from storage during this call, so that
+    /// each slot is read at most once.
+    storage_cache: RefCell<HashMap<(Address, H256), H256>>,
+    /// The Berlin precompiles, less the ones the owner disabled, with the
+    /// paused ones failing. Loaded once, when the engine is created.
+    precompiles: PrecompileSet,
+    /// Hash chained over the changes applied during this call, see
+    /// `BlockCheckpoint::changes_hash`.
+    #[cfg(feature = "block_commitment")]
+    changes_hash: H256,
 }
 
 // TODO: upgrade to Berlin HF
@@ -48,13 +69,51 @@ const CONFIG: &Config = &Config::istanbul();
 /// Key for storing the state of the engine.
 const STATE_KEY: &[u8; 6] = b"\0STATE";
 
+/// Longest code stored as a single value, well below the 4 MiB NEAR allows.
+/// Longer code is stored in chunks of this size.
+const CODE_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Key of the most EVM gas a single transaction may use.
+const TX_GAS_LIMIT_KEY: &[u8; 13] = b"\0TX_GAS_LIMIT";
+
+/// Key of the most transactions the engine executes per NEAR block.
+const BLOCK_TX_LIMIT_KEY: &[u8; 15] = b"\0BLOCK_TX_LIMIT";
+
+/// Key of the gas prices transactions may offer.
+const GAS_PRICE_BOUNDS_KEY: &[u8; 17] = b"\0GAS_PRICE_BOUNDS";
+
+/// Key of the addresses of the precompiles the owner disabled.
+const DISABLED_PRECOMPILES_KEY: &[u8; 21] = b"\0DISABLED_PRECOMPILES";
+
+/// Key of the storage slot `cleanup_storage` continues from.
+const CLEANUP_CURSOR_KEY: &[u8; 15] = b"\0CLEANUP_CURSOR";
+
 impl Engine {
     pub fn new(origin: Address) -> Self {
         Self::new_with_state(Engine::get_state(), origin)
     }
 
     pub fn new_with_state(state: EngineState, origin: Address) -> Self {
-        Self { state, origin }
+        Self {
+            state,
+            origin,
+            code_cache: RefCell::new(HashMap::new()),
+            storage_cache: RefCell::new(HashMap::new()),
+            precompiles: Self::load_precompiles(),
+            #[cfg(feature = "block_commitment")]
+            changes_hash: H256::default(),
+        }
+    }
+
+    fn load_precompiles() -> PrecompileSet {
+        // Berlin's precompiles only differ from Istanbul's in the EIP-2565
+        // modexp pricing, which the engine takes ahead of the rest of Berlin.
+        let mut set = PrecompileSet::berlin();
+        for disabled in Self::get_disabled_precompiles() {
+            set.remove(&Address(disabled));
+        }
+        set.pause_aurora(crate::emergency::paused_precompiles());
+        set
     }
 
     /// Saves state into the storage.
@@ -70,26 +129,175 @@ impl Engine {
         }
     }
 
+    /// Returns the most EVM gas a single transaction may use, unlimited by
+    /// default. This is independent of the NEAR gas attached to the call.
+    pub fn get_tx_gas_limit() -> u64 {
+        sdk::read_u64(TX_GAS_LIMIT_KEY).unwrap_or(u64::MAX)
+    }
+
+    pub fn set_tx_gas_limit(gas_limit: u64) {
+        sdk::write_storage(TX_GAS_LIMIT_KEY, &gas_limit.to_le_bytes());
+    }
+
+    /// Checks the gas limit a transaction declares.
+    pub fn check_gas_limit(gas_limit: &U256) -> Result<(), LimitError> {
+        if *gas_limit > U256::from(Self::get_tx_gas_limit()) {
+            return Err(LimitError::GasLimitTooHigh);
+        }
+        Ok(())
+    }
+
+    /// Returns the most transactions the engine executes per NEAR block,
+    /// unlimited by default.
+    pub fn get_block_tx_limit() -> u64 {
+        sdk::read_u64(BLOCK_TX_LIMIT_KEY).unwrap_or(u64::MAX)
+    }
+
+    pub fn set_block_tx_limit(limit: u64) {
+        sdk::write_storage(BLOCK_TX_LIMIT_KEY, &limit.to_le_bytes());
+    }
+
+    /// Checks that the current block can take another transaction, counting
+    /// the transactions in its checkpoint.
+    pub fn check_block_limit() -> Result<(), LimitError> {
+        let checkpoint = Self::get_block_checkpoint(sdk::block_index());
+        if checkpoint.transaction_count >= Self::get_block_tx_limit() {
+            return Err(LimitError::BlockLimitReached);
+        }
+        Ok(())
+    }
+
+    /// Returns the gas prices transactions may offer, any by default.
+    pub fn get_gas_price_bounds() -> GasPriceBounds {
+        match sdk::read_storage(GAS_PRICE_BOUNDS_KEY) {
+            None => GasPriceBounds {
+                min: [0u8; 32],
+                max: [0xff; 32],
+            },
+            Some(bytes) => GasPriceBounds::try_from_slice(&bytes).expect("ERR_DESER"),
+        }
+    }
+
+    pub fn set_gas_price_bounds(bounds: &GasPriceBounds) {
+        if U256::from_big_endian(&bounds.min) > U256::from_big_endian(&bounds.max) {
+            sdk::panic_utf8(b"ERR_INVALID_GAS_PRICE_BOUNDS");
+        }
+        sdk::write_storage(GAS_PRICE_BOUNDS_KEY, &bounds.try_to_vec().expect("ERR_SER"));
+    }
+
+    /// Checks the gas price a transaction offers, protecting senders from
+    /// mistyped prices and the engine from free spam.
+    pub fn check_gas_price(gas_price: &U256) -> Result<(), LimitError> {
+        let bounds = Self::get_gas_price_bounds();
+        if *gas_price < U256::from_big_endian(&bounds.min) {
+            return Err(LimitError::GasPriceTooLow);
+        }
+        if *gas_price > U256::from_big_endian(&bounds.max) {
+            return Err(LimitError::GasPriceTooHigh);
+        }
+        Ok(())
+    }
+
+    pub fn get_disabled_precompiles() -> Vec<RawAddress> {
+        match sdk::read_storage(DISABLED_PRECOMPILES_KEY) {
+            None => Vec::new(),
+            Some(bytes) => Vec::try_from_slice(&bytes).expect("ERR_DESER"),
+        }
+    }
+
+    /// Disables the given precompiles, and re-enables any others.
+    pub fn set_disabled_precompiles(addresses: &[RawAddress]) {
+        sdk::write_storage(
+            DISABLED_PRECOMPILES_KEY,
+            &addresses.try_to_vec().expect("ERR_SER"),
+        );
+    }
+
+    /// Stores code once per code hash, with the address referring to it.
+    ///
+    /// Code longer than `CODE_CHUNK_SIZE` is stored in chunks instead, along
+    /// with its length.
+    ///
+    /// Code is not reference counted, so code stays stored after the last
+    /// account using it is removed.
     pub fn set_code(address: &Address, code: &[u8]) {
-        sdk::write_storage(&address_to_key(KeyPrefix::Code, address), code);
+        let code_hash = keccak(code);
+        if code.len() <= CODE_CHUNK_SIZE {
+            let code_key = hash_to_key(KeyPrefix::Code, &code_hash);
+            if !sdk::storage_has_key(&code_key) {
+                sdk::write_storage(&code_key, code);
+            }
+        } else {
+            let head_key = hash_to_key(KeyPrefix::CodeChunk, &code_hash);
+            if !sdk::storage_has_key(&head_key) {
+                for (index, chunk) in code.chunks(CODE_CHUNK_SIZE).enumerate() {
+                    sdk::write_storage(&code_chunk_key(&code_hash, index as u32), chunk);
+                }
+                sdk::write_storage(&head_key, &(code.len() as u64).to_le_bytes());
+            }
+        }
+        sdk::write_storage(&address_to_key(KeyPrefix::CodeHash, address), &code_hash.0);
+        // Drop code stored by address before deduplication, if any.
+        sdk::remove_storage(&address_to_key(KeyPrefix::Code, address));
     }
 
     pub fn remove_code(address: &Address) {
+        sdk::remove_storage(&address_to_key(KeyPrefix::CodeHash, address));
         sdk::remove_storage(&address_to_key(KeyPrefix::Code, address))
     }
 
+    /// Returns the storage key of the code of an address: by code hash, or
+    /// by address for code stored before deduplication.
+    fn code_key(address: &Address) -> Vec<u8> {
+        match sdk::read_storage(&address_to_key(KeyPrefix::CodeHash, address)) {
+            Some(code_hash) => hash_to_key(KeyPrefix::Code, &H256::from_slice(&code_hash)).to_vec(),
+            None => address_to_key(KeyPrefix::Code, address).to_vec(),
+        }
+    }
+
+    /// Returns the length of code stored in chunks, if the code at `code_key`
+    /// is.
+    fn chunked_code_len(code_key: &[u8]) -> Option<usize> {
+        // Code stored by address is never chunked.
+        if code_key.len() != 33 {
+            return None;
+        }
+        let mut head_key = [0u8; 33];
+        head_key.copy_from_slice(code_key);
+        head_key[0] = KeyPrefix::CodeChunk as u8;
+        sdk::read_u64(&head_key).map(|len| len as usize)
+    }
+
     pub fn get_code(address: &Address) -> Vec<u8> {
-        sdk::read_storage(&address_to_key(KeyPrefix::Code, address)).unwrap_or_else(Vec::new)
+        let code_key = Self::code_key(address);
+        if let Some(code) = sdk::read_storage(&code_key) {
+            return code;
+        }
+        let len = match Self::chunked_code_len(&code_key) {
+            Some(len) => len,
+            None => return Vec::new(),
+        };
+        let code_hash = H256::from_slice(&code_key[1..]);
+        let mut code = Vec::with_capacity(len);
+        for index in 0..(len + CODE_CHUNK_SIZE - 1) / CODE_CHUNK_SIZE {
+            let chunk = sdk::read_storage(&code_chunk_key(&code_hash, index as u32))
+                .expect("ERR_CODE_CHUNK");
+            code.extend_from_slice(&chunk);
+        }
+        code
     }
 
     pub fn get_code_size(address: &Address) -> usize {
-        Engine::get_code(&address).len()
+        let code_key = Self::code_key(address);
+        sdk::read_storage_len(&code_key)
+            .or_else(|| Self::chunked_code_len(&code_key))
+            .unwrap_or(0)
     }
 
     pub fn set_nonce(address: &Address, nonce: &U256) {
         sdk::write_storage(
             &address_to_key(KeyPrefix::Nonce, address),
-            &u256_to_arr(nonce),
+            trim_value(&u256_to_arr(nonce)),
         );
     }
 
@@ -123,7 +331,7 @@ impl Engine {
     pub fn set_balance(address: &Address, balance: &U256) {
         sdk::write_storage(
             &address_to_key(KeyPrefix::Balance, address),
-            &u256_to_arr(balance),
+            trim_value(&u256_to_arr(balance)),
         );
     }
 
@@ -155,6 +363,17 @@ impl Engine {
         }
     }
 
+    /// Increases the balance of an address by an amount.
+    ///
+    /// # Errors
+    ///
+    /// * If the balance would be > `U256::MAX`
+    pub fn increase_balance(address: &Address, amount: &U256) -> Result<(), ExitError> {
+        let balance = Self::check_increase_balance(address, amount)?;
+        Self::set_balance(address, &balance);
+        Ok(())
+    }
+
     /// Checks if the balance can be decreased by an amount for a given address.
     ///
     /// Returns the new balance on success.
@@ -173,30 +392,133 @@ impl Engine {
         }
     }
 
-    pub fn remove_storage(address: &Address, key: &H256) {
-        sdk::remove_storage(&storage_to_key(address, key));
+    pub fn get_generation(address: &Address) -> u32 {
+        sdk::read_storage(&address_to_key(KeyPrefix::Generation, address))
+            .map(|value| {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&value);
+                u32::from_le_bytes(bytes)
+            })
+            .unwrap_or(0)
     }
 
-    pub fn set_storage(address: &Address, key: &H256, value: &H256) {
-        sdk::write_storage(&storage_to_key(address, key), &value.0);
+    fn set_generation(address: &Address, generation: u32) {
+        sdk::write_storage(
+            &address_to_key(KeyPrefix::Generation, address),
+            &generation.to_le_bytes(),
+        );
     }
 
     pub fn get_storage(address: &Address, key: &H256) -> H256 {
-        sdk::read_storage(&storage_to_key(address, key))
-            .map(|value| H256::from_slice(&value))
+        let generation = Self::get_generation(address);
+        sdk::read_storage(StorageKeys::new(address, generation).key(key))
+            .map(|value| H256(pad_value(&value)))
             .unwrap_or_else(H256::default)
     }
 
+    /// Returns how many bytes of NEAR storage the records of an address use,
+    /// as counted by the runtime's `storage_usage`.
+    ///
+    /// Only changes made by EVM execution are counted. Code shared by several
+    /// addresses counts against the first one to store it.
+    pub fn get_storage_usage(address: &Address) -> u64 {
+        sdk::read_u64(&address_to_key(KeyPrefix::StorageUsage, address)).unwrap_or(0)
+    }
+
+    fn add_storage_usage(address: &Address, delta: i64) {
+        if delta == 0 {
+            return;
+        }
+        let key = address_to_key(KeyPrefix::StorageUsage, address);
+        let usage = (Self::get_storage_usage(address) as i64).saturating_add(delta);
+        if usage > 0 {
+            sdk::write_storage(&key, &(usage as u64).to_le_bytes());
+        } else {
+            sdk::remove_storage(&key);
+        }
+    }
+
+    /// Reads only as much of the account as needed: most accounts that are
+    /// checked have a balance or nonce, so the code length is rarely read.
     pub fn is_account_empty(address: &Address) -> bool {
-        let balance = Self::get_balance(address);
-        let nonce = Self::get_nonce(address);
-        let code_len = Self::get_code_size(address);
-        balance == U256::zero() && nonce == U256::zero() && code_len == 0
+        Self::get_balance(address).is_zero()
+            && Self::get_nonce(address).is_zero()
+            && Self::get_code_size(address) == 0
     }
 
     /// Removes all storage for the given address.
-    pub fn remove_all_storage(_address: &Address) {
-        // FIXME: there is presently no way to prefix delete trie state.
+    ///
+    /// There is no way to delete storage by prefix, so instead the address
+    /// moves to its next storage generation and the old slots become
+    /// unreachable. They stay in storage until `cleanup_storage` gets to them.
+    pub fn remove_all_storage(address: &Address) {
+        let generation = match Self::get_generation(address).checked_add(1) {
+            Some(generation) => generation,
+            None => sdk::panic_utf8(b"ERR_GENERATION_OVERFLOW"),
+        };
+        Self::set_generation(address, generation);
+    }
+
+    /// Removes storage slots left behind in old storage generations by
+    /// `remove_all_storage`.
+    ///
+    /// Goes through at most `limit` storage slots, starting where the previous
+    /// call stopped and starting over after the last slot. Returns how many
+    /// slots were removed.
+    pub fn cleanup_storage(limit: u32) -> u32 {
+        let start = sdk::read_storage(CLEANUP_CURSOR_KEY)
+            .unwrap_or_else(|| [KeyPrefix::Storage as u8].to_vec());
+        let end = [KeyPrefix::Storage as u8 + 1];
+        let iterator = sdk::storage_iter_range(&start, &end);
+        let mut keys = Vec::new();
+        let mut next = None;
+        while let Some((key, _value)) = sdk::storage_iter_next(iterator) {
+            if keys.len() == limit as usize {
+                next = Some(key);
+                break;
+            }
+            keys.push(key);
+        }
+
+        // Storage is only changed once done with the iterator, which changes
+        // to storage would invalidate.
+        let mut removed = 0;
+        let mut current: Option<(Address, u32)> = None;
+        for key in keys {
+            let (address, generation) = match parse_key(&key) {
+                StorageKeyKind::Storage {
+                    address,
+                    generation,
+                    ..
+                } => (Address(address), generation),
+                _ => continue,
+            };
+            let live_generation = match current {
+                Some((current_address, live_generation)) if current_address == address => {
+                    live_generation
+                }
+                _ => {
+                    let live_generation = Self::get_generation(&address);
+                    current = Some((address, live_generation));
+                    live_generation
+                }
+            };
+            if generation < live_generation {
+                let usage_before = sdk::storage_usage();
+                sdk::remove_storage(&key);
+                Self::add_storage_usage(
+                    &address,
+                    sdk::storage_usage() as i64 - usage_before as i64,
+                );
+                removed += 1;
+            }
+        }
+
+        match next {
+            Some(key) => sdk::write_storage(CLEANUP_CURSOR_KEY, &key),
+            None => sdk::remove_storage(CLEANUP_CURSOR_KEY),
+        }
+        removed
     }
 
     /// Removes an account.
@@ -208,17 +530,49 @@ impl Engine {
     }
 
     /// Removes an account if it is empty.
+    ///
+    /// An empty account has no code and so no storage, which saves moving it
+    /// to a new storage generation every time an empty account is touched.
     pub fn remove_account_if_empty(address: &Address) {
         if Self::is_account_empty(address) {
-            Self::remove_account(address);
+            Self::remove_nonce(address);
+            Self::remove_balance(address);
+            Self::remove_code(address);
+        }
+    }
+
+    pub fn get_block_checkpoint(height: u64) -> BlockCheckpoint {
+        match sdk::read_storage(&block_to_key(height)) {
+            None => Default::default(),
+            Some(bytes) => BlockCheckpoint::try_from_slice(&bytes).expect("ERR_DESER"),
         }
     }
 
+    /// Adds an executed transaction to the checkpoint of the current block,
+    /// so that a re-execution of the chain can tell the first block at which
+    /// its results diverge.
+    fn record_transaction(&self, gas_used: u64) {
+        let height = sdk::block_index();
+        let mut checkpoint = Self::get_block_checkpoint(height);
+        checkpoint.transaction_count += 1;
+        checkpoint.gas_used = checkpoint.gas_used.saturating_add(gas_used);
+        #[cfg(feature = "block_commitment")]
+        {
+            checkpoint.changes_hash =
+                chain_hash(&H256(checkpoint.changes_hash), &self.changes_hash).0;
+        }
+        sdk::write_storage(
+            &block_to_key(height),
+            &checkpoint.try_to_vec().expect("ERR_SER"),
+        );
+    }
+
     /// Transfers an amount from a given sender to a receiver, provided that
     /// the have enough in their balance.
     ///
     /// If the sender can send, and the receiver can receive, then the transfer
-    /// will execute successfully.
+    /// will execute successfully, and be recorded in the block's checkpoint
+    /// as a transaction using the intrinsic gas of a call.
     pub fn transfer(&mut self, sender: &Address, receiver: &Address, value: &U256) -> ExitReason {
         let balance = Self::get_balance(sender);
         if balance < *value {
@@ -236,6 +590,7 @@ impl Engine {
 
         Self::set_balance(sender, &new_sender_balance);
         Self::set_balance(receiver, &new_receiver_balance);
+        self.record_transaction(CONFIG.gas_transaction_call);
 
         ExitReason::Succeed(ExitSucceed::Returned)
     }
@@ -255,11 +610,13 @@ impl Engine {
         let mut executor = self.make_executor();
         let address = executor.create_address(CreateScheme::Legacy { caller: origin });
         let (status, result) = (
-            executor.transact_create(origin, value, Vec::from(input), u64::MAX),
+            executor.transact_create(origin, value, Vec::from(input), Self::get_tx_gas_limit()),
             address,
         );
+        let gas_used = executor.used_gas();
         let (values, logs) = executor.into_state().deconstruct();
         self.apply(values, logs, true);
+        self.record_transaction(gas_used);
         (status, result)
     }
 
@@ -278,9 +635,12 @@ impl Engine {
         input: Vec<u8>,
     ) -> (ExitReason, Vec<u8>) {
         let mut executor = self.make_executor();
-        let (status, result) = executor.transact_call(origin, contract, value, input, u64::MAX);
+        let gas_limit = Self::get_tx_gas_limit();
+        let (status, result) = executor.transact_call(origin, contract, value, input, gas_limit);
+        let gas_used = executor.used_gas();
         let (values, logs) = executor.into_state().deconstruct();
         self.apply(values, logs, true);
+        self.record_transaction(gas_used);
         (status, result)
     }
 
@@ -314,7 +674,79 @@ impl Engine {
     fn make_executor(&self) -> StackExecutor<MemoryStackState<Engine>> {
         let metadata = StackSubstateMetadata::new(u64::MAX, &CONFIG);
         let state = MemoryStackState::new(metadata, self);
-        StackExecutor::new_with_precompile(state, &CONFIG, precompiles::istanbul_precompiles)
+        set_executor_precompiles(self.precompiles);
+        StackExecutor::new_with_precompile(state, &CONFIG, engine_precompiles)
+    }
+}
+
+/// The precompiles of the engine that made the last executor. The executor
+/// takes a plain function as its precompile hook, which cannot reach the
+/// engine, so `make_executor` leaves a copy of them here for
+/// `engine_precompiles`.
+#[cfg(target_arch = "wasm32")]
+struct ExecutorPrecompiles(Cell<Option<PrecompileSet>>);
+
+// The contract runs single-threaded.
+#[cfg(target_arch = "wasm32")]
+unsafe impl Sync for ExecutorPrecompiles {}
+
+#[cfg(target_arch = "wasm32")]
+static EXECUTOR_PRECOMPILES: ExecutorPrecompiles = ExecutorPrecompiles(Cell::new(None));
+
+#[cfg(target_arch = "wasm32")]
+fn set_executor_precompiles(precompiles: PrecompileSet) {
+    EXECUTOR_PRECOMPILES.0.set(Some(precompiles));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn executor_precompiles() -> Option<PrecompileSet> {
+    EXECUTOR_PRECOMPILES.0.get()
+}
+
+// Natively, as in tests, engines may run on several threads at once.
+#[cfg(not(target_arch = "wasm32"))]
+std::thread_local! {
+    static EXECUTOR_PRECOMPILES: Cell<Option<PrecompileSet>> = Cell::new(None);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_executor_precompiles(precompiles: PrecompileSet) {
+    EXECUTOR_PRECOMPILES.with(|set| set.set(Some(precompiles)));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn executor_precompiles() -> Option<PrecompileSet> {
+    EXECUTOR_PRECOMPILES.with(Cell::get)
+}
+
+/// Dispatches to the precompiles of the engine running the executor. They
+/// are copied out rather than borrowed, so a nested executor replacing them
+/// does not alias a running call.
+fn engine_precompiles(
+    address: Address,
+    input: &[u8],
+    target_gas: Option<u64>,
+    context: &Context,
+) -> Option<EvmPrecompileResult> {
+    executor_precompiles()?.run(address, input, target_gas, context)
+}
+
+/// Hashes `next` onto `previous`.
+#[cfg(feature = "block_commitment")]
+fn chain_hash(previous: &H256, next: &H256) -> H256 {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(previous.as_bytes());
+    bytes[32..].copy_from_slice(next.as_bytes());
+    keccak(&bytes)
+}
+
+#[cfg(feature = "block_commitment")]
+impl Engine {
+    /// Chains the hash of a change onto `changes_hash`. Each change hashes
+    /// the concatenation of its fields.
+    fn commit_change(&mut self, fields: &[&[u8]]) {
+        let change = keccak(&fields.concat());
+        self.changes_hash = chain_hash(&self.changes_hash, &change);
     }
 }
 
@@ -400,12 +832,20 @@ impl evm::backend::Backend for Engine {
 
     /// Returns the code of the contract from an address.
     fn code(&self, address: Address) -> Vec<u8> {
-        Engine::get_code(&address)
+        self.code_cache
+            .borrow_mut()
+            .entry(address)
+            .or_insert_with(|| Engine::get_code(&address))
+            .clone()
     }
 
     /// Get storage value of address at index.
     fn storage(&self, address: Address, index: H256) -> H256 {
-        Engine::get_storage(&address, &index)
+        *self
+            .storage_cache
+            .borrow_mut()
+            .entry((address, index))
+            .or_insert_with(|| Engine::get_storage(&address, &index))
     }
 
     /// Get original storage value of address at index, if available.
@@ -417,6 +857,12 @@ impl evm::backend::Backend for Engine {
 }
 
 impl ApplyBackend for Engine {
+    /// Writes the net changes of an executed call to storage.
+    ///
+    /// During execution all state changes, including `SSTORE`s, only go to
+    /// the journal of the `MemoryStackState`, and reverted call frames are
+    /// discarded from it. By the time the changes get here, each touched
+    /// storage slot has its final value, so it is written at most once.
     fn apply<A, I, L>(&mut self, values: A, logs: L, delete_empty: bool)
     where
         A: IntoIterator<Item = Apply<I>>,
@@ -424,7 +870,8 @@ impl ApplyBackend for Engine {
         L: IntoIterator<Item = Log>,
     {
         for apply in values {
-            match apply {
+            let usage_before = sdk::storage_usage();
+            let address = match apply {
                 Apply::Modify {
                     address,
                     basic,
@@ -434,32 +881,65 @@ impl ApplyBackend for Engine {
                 } => {
                     Engine::set_nonce(&address, &basic.nonce);
                     Engine::set_balance(&address, &basic.balance);
+                    #[cfg(feature = "block_commitment")]
+                    {
+                        let code_hash = code.as_ref().map(|code| keccak(code));
+                        self.commit_change(&[
+                            address.as_bytes(),
+                            &u256_to_arr(&basic.nonce),
+                            &u256_to_arr(&basic.balance),
+                            code_hash.as_ref().map_or(&[][..], |hash| hash.as_bytes()),
+                            &[reset_storage as u8],
+                        ]);
+                    }
                     if let Some(code) = code {
-                        Engine::set_code(&address, &code)
+                        Engine::set_code(&address, &code);
+                        self.code_cache.get_mut().insert(address, code);
                     }
 
                     if reset_storage {
-                        Engine::remove_all_storage(&address)
+                        Engine::remove_all_storage(&address);
+                        self.storage_cache.get_mut().clear();
                     }
 
+                    let mut keys = StorageKeys::new(&address, Engine::get_generation(&address));
                     for (index, value) in storage {
+                        // Slots written back with the value they already hold,
+                        // e.g. an allowance reset to its current value, are
+                        // skipped: a read costs less than a write.
+                        if self.storage(address, index) == value {
+                            continue;
+                        }
                         if value == H256::default() {
-                            Engine::remove_storage(&address, &index)
+                            sdk::remove_storage(keys.key(&index))
                         } else {
-                            Engine::set_storage(&address, &index, &value)
+                            sdk::write_storage(keys.key(&index), trim_value(&value.0))
                         }
+                        self.storage_cache.get_mut().insert((address, index), value);
+                        #[cfg(feature = "block_commitment")]
+                        self.commit_change(&[address.as_bytes(), &index.0, &value.0]);
                     }
 
                     if delete_empty {
                         Engine::remove_account_if_empty(&address)
                     }
+                    address
                 }
-                Apply::Delete { address } => Engine::remove_account(&address),
-            }
+                Apply::Delete { address } => {
+                    #[cfg(feature = "block_commitment")]
+                    self.commit_change(&[address.as_bytes()]);
+                    Engine::remove_account(&address);
+                    self.code_cache.get_mut().remove(&address);
+                    self.storage_cache.get_mut().clear();
+                    address
+                }
+            };
+            let usage_delta = sdk::storage_usage() as i64 - usage_before as i64;
+            Engine::add_storage_usage(&address, usage_delta);
         }
 
         for log in logs {
-            sdk::log_utf8(&bytes_to_hex(&log_to_bytes(log)).into_bytes())
+            sdk::log_utf8(log_to_hex(&log).as_bytes())
         }
     }
 }
@@ -9,12 +9,20 @@ extern crate core;
 
 pub mod meta_parsing;
 pub mod parameters;
-mod precompiles;
+pub mod precompiles;
 pub mod prelude;
 mod storage;
-mod transaction;
+pub mod transaction;
 pub mod types;
 
+#[cfg(feature = "contract")]
+mod account_override;
+#[cfg(feature = "contract")]
+mod audit_log;
+#[cfg(all(feature = "contract", feature = "bump_alloc", target_arch = "wasm32"))]
+mod bump_alloc;
+#[cfg(feature = "contract")]
+mod emergency;
 #[cfg(feature = "contract")]
 mod engine;
 #[cfg(feature = "contract")]
@@ -22,7 +30,13 @@ mod json;
 #[cfg(feature = "contract")]
 mod log_entry;
 #[cfg(feature = "contract")]
+mod migration;
+#[cfg(feature = "contract")]
+mod relayers;
+#[cfg(feature = "contract")]
 mod sdk;
+#[cfg(feature = "contract")]
+mod state_export;
 
 #[cfg(feature = "contract")]
 mod contract {
@@ -30,19 +44,30 @@ mod contract {
     use evm::{ExitError, ExitFatal, ExitReason};
 
     use crate::engine::{Engine, EngineState};
+    use crate::parameters::{
+        AccountOverrideArgs, AllowRelayerArgs, ExportStateArgs, FunctionCallArgs, GasPriceBounds,
+        GetAuditLogArgs, GetStorageAtArgs, HashStateArgs, NewCallArgs, StateChunk, ViewCallArgs,
+    };
     #[cfg(feature = "evm_bully")]
     use crate::parameters::{BeginBlockArgs, BeginChainArgs};
-    use crate::parameters::{FunctionCallArgs, GetStorageAtArgs, NewCallArgs, ViewCallArgs};
-    use crate::prelude::{vec, Address, H256, U256};
+    use crate::prelude::{vec, Address, String, Vec, H256, U256};
     use crate::sdk;
-    use crate::types::{near_account_to_evm_address, u256_to_arr};
+    use crate::types::{near_account_to_evm_address, u256_to_arr, RawAddress};
 
+    #[cfg(not(all(feature = "bump_alloc", target_arch = "wasm32")))]
     #[global_allocator]
     static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+    #[cfg(all(feature = "bump_alloc", target_arch = "wasm32"))]
+    #[global_allocator]
+    static ALLOC: crate::bump_alloc::BumpAlloc = crate::bump_alloc::BumpAlloc::INIT;
+
     const CODE_KEY: &[u8; 5] = b"\0CODE";
     const CODE_STAGE_KEY: &[u8; 11] = b"\0CODE_STAGE";
 
+    /// Gas `deploy_upgrade` keeps for itself, the rest goes to `migrate`.
+    const DEPLOY_UPGRADE_GAS: u64 = 10_000_000_000_000;
+
     #[cfg(target_arch = "wasm32")]
     #[panic_handler]
     #[no_mangle]
@@ -69,8 +94,10 @@ mod contract {
         if !state.owner_id.is_empty() {
             require_owner_only(&state);
         }
+        audit("new");
         let args = NewCallArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
         Engine::set_state(args.into());
+        crate::migration::set_version(crate::migration::CURRENT_VERSION);
     }
 
     /// Get version of the contract.
@@ -115,6 +142,7 @@ mod contract {
     pub extern "C" fn stage_upgrade() {
         let state = Engine::get_state();
         require_owner_only(&state);
+        audit("stage_upgrade");
         sdk::read_input_and_store(CODE_KEY);
         sdk::write_storage(CODE_STAGE_KEY, &sdk::block_index().to_le_bytes());
     }
@@ -127,7 +155,308 @@ mod contract {
         if sdk::block_index() <= index + state.upgrade_delay_blocks {
             sdk::panic_utf8(b"ERR_NOT_ALLOWED:TOO_EARLY");
         }
-        sdk::self_deploy(CODE_KEY);
+        audit("deploy_upgrade");
+        sdk::self_deploy(CODE_KEY, b"migrate", DEPLOY_UPGRADE_GAS);
+    }
+
+    /// Runs pending state migrations. Called by `deploy_upgrade` right after
+    /// the new code is deployed.
+    ///
+    /// The staged code is removed only once the migrations succeed; until
+    /// then `deploy_upgrade` can be retried, e.g. with more gas.
+    #[no_mangle]
+    pub extern "C" fn migrate() {
+        sdk::assert_private_call();
+        crate::migration::migrate();
+        sdk::remove_storage(CODE_KEY);
+    }
+
+    /// Switch the storage namespace the engine serves, see `sdk::set_namespace`.
+    /// A namespace without engine state yet starts with the configuration of
+    /// the current one, so the owner stays in control of it.
+    #[no_mangle]
+    pub extern "C" fn set_storage_namespace() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_storage_namespace");
+        sdk::set_namespace(&sdk::read_input());
+        if Engine::get_state().owner_id.is_empty() {
+            Engine::set_state(state);
+            crate::migration::set_version(crate::migration::CURRENT_VERSION);
+        }
+    }
+
+    /// Get the storage namespace the engine serves, empty by default.
+    #[no_mangle]
+    pub extern "C" fn get_storage_namespace() {
+        sdk::return_output(&sdk::get_namespace())
+    }
+
+    /// Get the version of the stored state, see `migration`.
+    #[no_mangle]
+    pub extern "C" fn get_state_version() {
+        sdk::return_output(&crate::migration::get_version().to_le_bytes())
+    }
+
+    /// Set the most EVM gas a single transaction may use.
+    #[no_mangle]
+    pub extern "C" fn set_tx_gas_limit() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_tx_gas_limit");
+        let gas_limit = u64::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        Engine::set_tx_gas_limit(gas_limit);
+    }
+
+    /// Get the most EVM gas a single transaction may use.
+    #[no_mangle]
+    pub extern "C" fn get_tx_gas_limit() {
+        sdk::return_output(&Engine::get_tx_gas_limit().to_le_bytes())
+    }
+
+    /// Set the most EVM transactions the engine executes per NEAR block.
+    #[no_mangle]
+    pub extern "C" fn set_block_tx_limit() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_block_tx_limit");
+        let limit = u64::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        Engine::set_block_tx_limit(limit);
+    }
+
+    /// Get the most EVM transactions the engine executes per NEAR block.
+    #[no_mangle]
+    pub extern "C" fn get_block_tx_limit() {
+        sdk::return_output(&Engine::get_block_tx_limit().to_le_bytes())
+    }
+
+    /// Set the lowest and highest gas price transactions may offer.
+    #[no_mangle]
+    pub extern "C" fn set_gas_price_bounds() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_gas_price_bounds");
+        let bounds = GasPriceBounds::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        Engine::set_gas_price_bounds(&bounds);
+    }
+
+    /// Get the lowest and highest gas price transactions may offer.
+    #[no_mangle]
+    pub extern "C" fn get_gas_price_bounds() {
+        use borsh::BorshSerialize;
+
+        let bounds = Engine::get_gas_price_bounds();
+        sdk::return_output(&bounds.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Disable the precompiles at the given addresses and re-enable all
+    /// others. The address of a disabled precompile behaves like an account
+    /// without code.
+    #[no_mangle]
+    pub extern "C" fn set_disabled_precompiles() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_disabled_precompiles");
+        let addresses =
+            Vec::<RawAddress>::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        Engine::set_disabled_precompiles(&addresses);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_disabled_precompiles() {
+        use borsh::BorshSerialize;
+
+        let addresses = Engine::get_disabled_precompiles();
+        sdk::return_output(&addresses.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Turn the relayer allowlist on or off, see `relayers`.
+    #[no_mangle]
+    pub extern "C" fn set_relayer_allowlist() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_relayer_allowlist");
+        let enabled = bool::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::relayers::set_allowlist_enabled(enabled);
+    }
+
+    /// Add a relayer to the allowlist, or change its quota.
+    #[no_mangle]
+    pub extern "C" fn allow_relayer() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("allow_relayer");
+        let args = AllowRelayerArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::relayers::allow(args.account_id.as_bytes(), args.transactions_per_block);
+    }
+
+    /// Remove a relayer from the allowlist.
+    #[no_mangle]
+    pub extern "C" fn disallow_relayer() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("disallow_relayer");
+        crate::relayers::disallow(&sdk::read_input());
+    }
+
+    /// Get the quota of a relayer, `None` if it is not on the allowlist.
+    #[no_mangle]
+    pub extern "C" fn get_relayer_quota() {
+        use borsh::BorshSerialize;
+
+        let quota = crate::relayers::get_quota(&sdk::read_input());
+        sdk::return_output(&quota.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Set the emergency account, which can only pause methods, see
+    /// `emergency`. An empty account id removes it.
+    #[no_mangle]
+    pub extern "C" fn set_emergency_account() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("set_emergency_account");
+        crate::emergency::set_emergency_id(&sdk::read_input());
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_emergency_account() {
+        sdk::return_output(&crate::emergency::get_emergency_id())
+    }
+
+    /// Pause a mutative method. The owner or the emergency account can call
+    /// this.
+    #[no_mangle]
+    pub extern "C" fn pause_method() {
+        let state = Engine::get_state();
+        let predecessor = sdk::predecessor_account_id();
+        if state.owner_id.as_bytes() != predecessor
+            && !crate::emergency::is_emergency_account(&predecessor)
+        {
+            sdk::panic_utf8(b"ERR_NOT_ALLOWED");
+        }
+        audit("pause_method");
+        let method = String::from_utf8(sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::emergency::pause(&method);
+    }
+
+    /// Resume a paused method. Only the owner can call this.
+    #[no_mangle]
+    pub extern "C" fn resume_method() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("resume_method");
+        let method = String::from_utf8(sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::emergency::resume(&method);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_paused_methods() {
+        use borsh::BorshSerialize;
+
+        let methods = crate::emergency::paused_methods();
+        sdk::return_output(&methods.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Pause the Aurora-specific precompiles whose flags are set in the
+    /// `u32` mask, so that calls to them fail. The owner or the emergency
+    /// account can call this.
+    #[no_mangle]
+    pub extern "C" fn pause_precompiles() {
+        let state = Engine::get_state();
+        let predecessor = sdk::predecessor_account_id();
+        if state.owner_id.as_bytes() != predecessor
+            && !crate::emergency::is_emergency_account(&predecessor)
+        {
+            sdk::panic_utf8(b"ERR_NOT_ALLOWED");
+        }
+        audit("pause_precompiles");
+        let mask = u32::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::emergency::pause_precompiles(mask);
+    }
+
+    /// Resume the paused precompiles whose flags are set in the `u32` mask.
+    /// Only the owner can call this.
+    #[no_mangle]
+    pub extern "C" fn resume_precompiles() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("resume_precompiles");
+        let mask = u32::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::emergency::resume_precompiles(mask);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn get_paused_precompiles() {
+        sdk::return_output(&crate::emergency::paused_precompiles().to_le_bytes())
+    }
+
+    /// Stage an override of an account's balance or nonce, see
+    /// `account_override`.
+    #[no_mangle]
+    pub extern "C" fn stage_account_override() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("stage_account_override");
+        let args = AccountOverrideArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        crate::account_override::stage(args);
+    }
+
+    /// Apply the staged account override once its delay has passed.
+    #[no_mangle]
+    pub extern "C" fn apply_account_override() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("apply_account_override");
+        crate::account_override::apply(state.upgrade_delay_blocks);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn cancel_account_override() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("cancel_account_override");
+        crate::account_override::cancel();
+    }
+
+    /// Get the staged account override, if any.
+    #[no_mangle]
+    pub extern "C" fn get_account_override() {
+        use borsh::BorshSerialize;
+
+        let args = crate::account_override::get_staged_args();
+        sdk::return_output(&args.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Get entries of the administrative actions log, see `audit_log`.
+    #[no_mangle]
+    pub extern "C" fn get_audit_log() {
+        use borsh::BorshSerialize;
+
+        let args = GetAuditLogArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        let entries = crate::audit_log::entries(args.from, args.limit);
+        sdk::return_output(&entries.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Export a chunk of the EVM state, see `state_export`.
+    #[no_mangle]
+    pub extern "C" fn export_state() {
+        use borsh::BorshSerialize;
+
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        let args = ExportStateArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        let chunk = crate::state_export::export(args);
+        sdk::return_output(&chunk.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Import a chunk of EVM state exported from another deployment.
+    #[no_mangle]
+    pub extern "C" fn import_state() {
+        let state = Engine::get_state();
+        require_owner_only(&state);
+        audit("import_state");
+        let chunk = StateChunk::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        sdk::return_output(&crate::state_export::import(chunk))
     }
 
     ///
@@ -137,6 +466,8 @@ mod contract {
     /// Deploy code into the EVM.
     #[no_mangle]
     pub extern "C" fn deploy_code() {
+        crate::emergency::require_running("deploy_code");
+        Engine::check_block_limit().sdk_unwrap();
         let input = sdk::read_input();
         let mut engine = Engine::new(predecessor_address());
         let (status, address) = Engine::deploy_code_with_input(&mut engine, &input);
@@ -147,6 +478,8 @@ mod contract {
     /// Call method on the EVM contract.
     #[no_mangle]
     pub extern "C" fn call() {
+        crate::emergency::require_running("call");
+        Engine::check_block_limit().sdk_unwrap();
         let input = sdk::read_input();
         let args = FunctionCallArgs::try_from_slice(&input).expect("ERR_ARG_PARSE");
         let mut engine = Engine::new(predecessor_address());
@@ -162,6 +495,10 @@ mod contract {
         use crate::transaction::EthSignedTransaction;
         use rlp::{Decodable, Rlp};
 
+        crate::emergency::require_running("raw_call");
+        // Checked once for all three kinds of transaction below, each of
+        // which the engine records in the block's checkpoint.
+        Engine::check_block_limit().sdk_unwrap();
         let input = sdk::read_input();
         let signed_transaction = EthSignedTransaction::decode(&Rlp::new(&input))
             .map_err(|_| ())
@@ -184,6 +521,9 @@ mod contract {
 
         let next_nonce =
             Engine::check_nonce(&sender, &signed_transaction.transaction.nonce).sdk_unwrap();
+        Engine::check_gas_limit(&signed_transaction.transaction.gas).sdk_unwrap();
+        Engine::check_gas_price(&signed_transaction.transaction.gas_price).sdk_unwrap();
+        crate::relayers::use_quota(&sdk::predecessor_account_id()).sdk_unwrap();
 
         // Figure out what kind of a transaction this is, and execute it:
         let mut engine = Engine::new_with_state(state, sender);
@@ -215,6 +555,8 @@ mod contract {
 
     #[no_mangle]
     pub extern "C" fn meta_call() {
+        crate::emergency::require_running("meta_call");
+        Engine::check_block_limit().sdk_unwrap();
         let input = sdk::read_input();
         let state = Engine::get_state();
         let domain_separator = crate::meta_parsing::near_erc712_domain(U256::from(state.chain_id));
@@ -241,6 +583,15 @@ mod contract {
         process_exit_reason(status, &result);
     }
 
+    /// Remove storage left behind by self-destructed contracts, going through
+    /// at most the given number of storage slots. Anyone can call this.
+    #[no_mangle]
+    pub extern "C" fn cleanup() {
+        let limit = u32::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        let removed = Engine::cleanup_storage(limit);
+        sdk::return_output(&removed.to_le_bytes())
+    }
+
     #[cfg(feature = "testnet")]
     #[no_mangle]
     pub extern "C" fn make_it_rain() {
@@ -285,6 +636,44 @@ mod contract {
         sdk::return_output(&u256_to_arr(&nonce))
     }
 
+    /// Get the checkpoint of the NEAR block at the given height.
+    #[no_mangle]
+    pub extern "C" fn get_block_checkpoint() {
+        use borsh::BorshSerialize;
+
+        let height = u64::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        let checkpoint = Engine::get_block_checkpoint(height);
+        sdk::return_output(&checkpoint.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Get the bytes of NEAR storage used by an EVM account.
+    #[no_mangle]
+    pub extern "C" fn get_storage_usage() {
+        let address = sdk::read_input_arr20();
+        let usage = Engine::get_storage_usage(&Address(address));
+        sdk::return_output(&usage.to_le_bytes())
+    }
+
+    /// Hash the engine state in chunks, see `state_export`.
+    #[no_mangle]
+    pub extern "C" fn hash_state() {
+        use borsh::BorshSerialize;
+
+        let args = HashStateArgs::try_from_slice(&sdk::read_input()).expect("ERR_ARG_PARSE");
+        let hash = crate::state_export::hash(args);
+        sdk::return_output(&hash.try_to_vec().expect("ERR_SER"))
+    }
+
+    /// Tells what a raw engine storage key refers to, for auditing and
+    /// migration tooling.
+    #[no_mangle]
+    pub extern "C" fn parse_storage_key() {
+        use borsh::BorshSerialize;
+
+        let kind = crate::storage::parse_key(&sdk::read_input());
+        sdk::return_output(&kind.try_to_vec().expect("ERR_SER"))
+    }
+
     #[no_mangle]
     pub extern "C" fn get_storage_at() {
         let input = sdk::read_input();
@@ -337,6 +726,11 @@ mod contract {
         }
     }
 
+    /// Records a call of an administrative method in the audit log.
+    fn audit(method: &str) {
+        crate::audit_log::record(method, sdk::predecessor_account_id(), &sdk::read_input());
+    }
+
     fn predecessor_address() -> Address {
         near_account_to_evm_address(&sdk::predecessor_account_id())
     }
@@ -395,6 +789,19 @@ mod contract {
         }
     }
 
+    impl ToStr for crate::types::LimitError {
+        fn to_str(&self) -> &str {
+            match self {
+                Self::GasLimitTooHigh => "ERR_GAS_LIMIT_TOO_HIGH",
+                Self::RelayerNotAllowed => "ERR_RELAYER_NOT_ALLOWED",
+                Self::RelayerQuotaExceeded => "ERR_RELAYER_QUOTA_EXCEEDED",
+                Self::GasPriceTooLow => "ERR_GAS_PRICE_TOO_LOW",
+                Self::GasPriceTooHigh => "ERR_GAS_PRICE_TOO_HIGH",
+                Self::BlockLimitReached => "ERR_BLOCK_LIMIT_REACHED",
+            }
+        }
+    }
+
     trait SdkUnwrap<T, E> {
         fn sdk_unwrap(self) -> T;
     }
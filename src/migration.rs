@@ -0,0 +1,55 @@
+//! State schema versioning.
+//!
+//! The version of the stored state is kept under its own key. `MIGRATIONS[i]`
+//! brings the state from version `i` to version `i + 1`; deployments from
+//! before versioning have no version key and are at version 0.
+//!
+//! A migration is added by appending it to `MIGRATIONS`. They run right after
+//! an upgrade is deployed, see `deploy_upgrade`.
+
+use crate::sdk;
+
+const STATE_VERSION_KEY: &[u8; 14] = b"\0STATE_VERSION";
+
+type Migration = fn();
+
+/// Registered migrations, oldest first.
+#[cfg(not(feature = "migration_test"))]
+const MIGRATIONS: &[Migration] = &[];
+
+/// The upgrade tests build the engine with one extra migration, heavy enough
+/// to fail when `deploy_upgrade` gets too little gas.
+#[cfg(feature = "migration_test")]
+const MIGRATIONS: &[Migration] = &[test_migration];
+
+#[cfg(feature = "migration_test")]
+fn test_migration() {
+    let mut hash = crate::types::keccak(b"test_migration");
+    for _ in 0..20_000 {
+        hash = crate::types::keccak(hash.as_bytes());
+    }
+    sdk::write_storage(b"\0TEST_MIGRATION", hash.as_bytes());
+}
+
+/// The state version this code works with.
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+pub fn get_version() -> u64 {
+    sdk::read_u64(STATE_VERSION_KEY).unwrap_or(0)
+}
+
+pub fn set_version(version: u64) {
+    sdk::write_storage(STATE_VERSION_KEY, &version.to_le_bytes());
+}
+
+/// Runs the migrations the stored state has not had yet.
+pub fn migrate() {
+    let version = get_version();
+    if version > CURRENT_VERSION {
+        sdk::panic_utf8(b"ERR_STATE_VERSION_TOO_NEW");
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration();
+    }
+    set_version(CURRENT_VERSION);
+}
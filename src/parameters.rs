@@ -89,6 +89,146 @@ pub struct BeginBlockArgs {
     pub gaslimit: RawU256,
 }
 
+/// Borsh-encoded entry of the administrative actions log, returned by the
+/// `get_audit_log` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub struct AuditLogEntry {
+    pub block_height: u64,
+    pub caller: AccountId,
+    pub method: String,
+    /// Keccak hash of the method's input.
+    pub input_hash: RawH256,
+}
+
+/// Borsh-encoded parameters for the `get_audit_log` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct GetAuditLogArgs {
+    /// Index of the first entry to return.
+    pub from: u64,
+    /// Maximum number of entries to return.
+    pub limit: u64,
+}
+
+/// Borsh-encoded parameters for the `stage_account_override` function, also
+/// returned by `get_account_override`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountOverrideArgs {
+    pub address: RawAddress,
+    /// New balance, `None` to leave it as it is.
+    pub balance: Option<RawU256>,
+    /// New nonce, `None` to leave it as it is.
+    pub nonce: Option<RawU256>,
+    /// Hash of the published justification for the override.
+    pub justification_hash: RawH256,
+}
+
+/// Borsh-encoded parameters for the `allow_relayer` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AllowRelayerArgs {
+    pub account_id: AccountId,
+    /// Most `raw_call` transactions the relayer may submit per NEAR block.
+    pub transactions_per_block: u64,
+}
+
+/// Borsh-encoded parameters for the `set_gas_price_bounds` function, also
+/// returned by `get_gas_price_bounds`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct GasPriceBounds {
+    /// Lowest gas price a transaction may offer, inclusive.
+    pub min: RawU256,
+    /// Highest gas price a transaction may offer, inclusive.
+    pub max: RawU256,
+}
+
+/// Borsh-encoded summary of the transactions the engine executed in a NEAR
+/// block, returned by the `get_block_checkpoint` function.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Eq, PartialEq)]
+pub struct BlockCheckpoint {
+    /// Number of EVM transactions executed in the block.
+    pub transaction_count: u64,
+    /// EVM gas used by those transactions in total.
+    pub gas_used: u64,
+    /// Hash chained over every account and storage slot the transactions
+    /// changed, in order. Only kept with the `block_commitment` feature, zero
+    /// otherwise.
+    pub changes_hash: RawH256,
+}
+
+/// Borsh-encoded parameters for the `export_state` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ExportStateArgs {
+    /// Key to start the chunk at, the `next` key of the previous chunk.
+    pub from: Vec<u8>,
+    /// Maximum number of entries in the chunk.
+    pub limit: u32,
+    /// Manifest hash of the previous chunk, zero for the first one.
+    pub manifest: RawH256,
+}
+
+/// Borsh-encoded chunk of engine state, returned by `export_state` and taken
+/// by `import_state`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StateChunk {
+    /// Raw storage keys and values, in key order.
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Key to export the next chunk from, `None` for the last chunk.
+    pub next: Option<Vec<u8>>,
+    /// Hash of these entries chained onto the previous chunk's manifest.
+    pub manifest: RawH256,
+}
+
+/// Borsh-encoded parameters for the `hash_state` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct HashStateArgs {
+    /// Key to start at, the `next` key of the previous call.
+    pub from: Vec<u8>,
+    /// Maximum number of keys to go through.
+    pub limit: u32,
+    /// Manifest hash returned by the previous call, zero for the first one.
+    pub manifest: RawH256,
+    /// Prefixes of the keys to leave out of the hash.
+    pub exclude: Vec<Vec<u8>>,
+}
+
+/// Borsh-encoded result of the `hash_state` function.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StateHash {
+    /// Key to continue from, `None` once all keys are hashed.
+    pub next: Option<Vec<u8>>,
+    /// Hash of the entries so far chained onto the previous manifest.
+    pub manifest: RawH256,
+}
+
+/// Borsh-encoded result of the `parse_storage_key` function: what a raw engine
+/// storage key refers to.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Eq, PartialEq)]
+pub enum StorageKeyKind {
+    /// Engine configuration, e.g. `\0STATE`; holds the key without the prefix.
+    Config(Vec<u8>),
+    Nonce(RawAddress),
+    Balance(RawAddress),
+    /// Code stored by address, from before code was stored by hash.
+    CodeByAddress(RawAddress),
+    CodeByHash(RawH256),
+    CodeHash(RawAddress),
+    Generation(RawAddress),
+    Storage {
+        address: RawAddress,
+        generation: u32,
+        slot: RawH256,
+    },
+    StorageUsage(RawAddress),
+    /// Checkpoint of the NEAR block at the given height.
+    BlockCheckpoint(u64),
+    /// Length of code stored in chunks, keyed by the code hash.
+    CodeChunkHead(RawH256),
+    CodeChunk {
+        code_hash: RawH256,
+        index: u32,
+    },
+    Unknown,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
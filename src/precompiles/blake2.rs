@@ -1,6 +1,6 @@
-use crate::precompiles::{Precompile, PrecompileResult};
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
 use crate::prelude::{mem, Borrowed, TryInto};
-use evm::{Context, ExitError, ExitSucceed};
+use evm::{Context, ExitError};
 
 /// Blake2 costs.
 mod costs {
@@ -17,6 +17,11 @@ pub(super) struct Blake2F;
 
 impl Precompile for Blake2F {
     fn required_gas(input: &[u8]) -> Result<u64, ExitError> {
+        if input.len() < mem::size_of::<u32>() {
+            return Err(ExitError::Other(Borrowed(
+                "input length invalid, must be 213 bytes",
+            )));
+        }
         let (int_bytes, _) = input.split_at(mem::size_of::<u32>());
         Ok(u64::from(u32::from_be_bytes(
             int_bytes.try_into().expect("cannot fail"),
@@ -44,7 +49,8 @@ impl Precompile for Blake2F {
         rounds_bytes.copy_from_slice(&input[0..4]);
         let rounds = u32::from_be_bytes(rounds_bytes);
 
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             return Err(ExitError::OutOfGas);
         }
 
@@ -80,13 +86,14 @@ impl Precompile for Blake2F {
         let finished = input[212] != 0;
 
         let res = blake2::blake2b_f(rounds, h, m, t, finished).to_vec();
-        Ok((ExitSucceed::Returned, res, 0))
+        Ok(PrecompileOutput::without_logs(cost, res))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
     use crate::prelude::Vec;
 
     fn new_context() -> Context {
@@ -194,12 +201,12 @@ mod tests {
             01",
         )
         .unwrap();
-        Blake2F::run(&input, 12, &new_context()).unwrap().1
+        Blake2F::run(&input, 12, &new_context()).unwrap().output
     }
 
     fn test_blake2f_r_12() -> Vec<u8> {
         let input = hex::decode(INPUT).unwrap();
-        Blake2F::run(&input, 12, &new_context()).unwrap().1
+        Blake2F::run(&input, 12, &new_context()).unwrap().output
     }
 
     fn test_blake2f_final_block_false() -> Vec<u8> {
@@ -217,7 +224,7 @@ mod tests {
             00",
         )
         .unwrap();
-        Blake2F::run(&input, 12, &new_context()).unwrap().1
+        Blake2F::run(&input, 12, &new_context()).unwrap().output
     }
 
     #[test]
@@ -276,4 +283,20 @@ mod tests {
         .unwrap();
         assert_eq!(test_blake2f_final_block_false(), expected);
     }
+
+    #[test]
+    fn test_blake2f_vectors() {
+        test_vectors::check_vectors::<Blake2F>("blake2F.json");
+        test_vectors::check_failures::<Blake2F>("fail-blake2f.json");
+    }
+
+    #[test]
+    fn test_blake2f_required_gas_short_input() {
+        assert!(matches!(
+            Blake2F::required_gas(&[0, 0, 1]),
+            Err(ExitError::Other(Borrowed(
+                "input length invalid, must be 213 bytes"
+            )))
+        ));
+    }
 }
@@ -1,6 +1,8 @@
-use crate::precompiles::{Byzantium, HardFork, Istanbul, Precompile, PrecompileResult};
+use crate::precompiles::{
+    Byzantium, HardFork, Istanbul, Precompile, PrecompileOutput, PrecompileResult,
+};
 use crate::prelude::*;
-use evm::{Context, ExitError, ExitSucceed};
+use evm::{Context, ExitError};
 
 /// bn128 costs.
 mod costs {
@@ -67,7 +69,7 @@ fn read_point(input: &[u8], pos: usize) -> Result<bn::G1, ExitError> {
 pub(super) struct BN128Add<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> BN128Add<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> PrecompileResult {
+    fn run_inner(input: &[u8], cost: u64, _context: &Context) -> PrecompileResult {
         use bn::AffineG1;
 
         let mut input = input.to_vec();
@@ -84,7 +86,7 @@ impl<HF: HardFork> BN128Add<HF> {
             output[32..64].copy_from_slice(&y);
         }
 
-        Ok((ExitSucceed::Returned, output.to_vec(), 0))
+        Ok(PrecompileOutput::without_logs(cost, output.to_vec()))
     }
 }
 
@@ -99,10 +101,11 @@ impl Precompile for BN128Add<Byzantium> {
     /// See: https://eips.ethereum.org/EIPS/eip-196
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000006
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -118,10 +121,11 @@ impl Precompile for BN128Add<Istanbul> {
     /// See: https://eips.ethereum.org/EIPS/eip-196
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000006
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -129,7 +133,7 @@ impl Precompile for BN128Add<Istanbul> {
 pub(super) struct BN128Mul<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> BN128Mul<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> PrecompileResult {
+    fn run_inner(input: &[u8], cost: u64, _context: &Context) -> PrecompileResult {
         use bn::AffineG1;
 
         let mut input = input.to_vec();
@@ -149,7 +153,7 @@ impl<HF: HardFork> BN128Mul<HF> {
             output[32..64].copy_from_slice(&y);
         }
 
-        Ok((ExitSucceed::Returned, output.to_vec(), 0))
+        Ok(PrecompileOutput::without_logs(cost, output.to_vec()))
     }
 }
 
@@ -163,10 +167,11 @@ impl Precompile for BN128Mul<Byzantium> {
     /// See: https://eips.ethereum.org/EIPS/eip-196
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000007
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -181,10 +186,11 @@ impl Precompile for BN128Mul<Istanbul> {
     /// See: https://eips.ethereum.org/EIPS/eip-196
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000007
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -192,7 +198,7 @@ impl Precompile for BN128Mul<Istanbul> {
 pub(super) struct BN128Pair<HF: HardFork>(PhantomData<HF>);
 
 impl<HF: HardFork> BN128Pair<HF> {
-    fn run_inner(input: &[u8], _context: &Context) -> PrecompileResult {
+    fn run_inner(input: &[u8], cost: u64, _context: &Context) -> PrecompileResult {
         use bn::{arith::U256, AffineG1, AffineG2, Fq, Fq2, Group, Gt, G1, G2};
 
         if input.len() % consts::PAIR_ELEMENT_LEN != 0 {
@@ -287,7 +293,10 @@ impl<HF: HardFork> BN128Pair<HF> {
             }
         };
 
-        Ok((ExitSucceed::Returned, output.to_big_endian().to_vec(), 0))
+        Ok(PrecompileOutput::without_logs(
+            cost,
+            output.to_big_endian().to_vec(),
+        ))
     }
 }
 
@@ -304,10 +313,11 @@ impl Precompile for BN128Pair<Byzantium> {
     /// See: https://eips.ethereum.org/EIPS/eip-197
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000008
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -325,10 +335,11 @@ impl Precompile for BN128Pair<Istanbul> {
     /// See: https://eips.ethereum.org/EIPS/eip-197
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000008
     fn run(input: &[u8], target_gas: u64, context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Self::run_inner(input, context)
+            Self::run_inner(input, cost, context)
         }
     }
 }
@@ -336,6 +347,8 @@ impl Precompile for BN128Pair<Istanbul> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
+    use proptest::prelude::*;
 
     fn new_context() -> Context {
         Context {
@@ -347,266 +360,119 @@ mod tests {
 
     #[test]
     fn test_alt_bn128_add() {
-        let input = hex::decode(
-            "\
-             18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
-             063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
-             07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
-             06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
-            301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915",
-        )
-        .unwrap();
-
-        let res = BN128Add::<Byzantium>::run(&input, 500, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // zero sum test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
+        test_vectors::check_vectors::<BN128Add<Istanbul>>("bn256Add.json");
+        test_vectors::check_failures::<BN128Add<Istanbul>>("fail-bn256Add.json");
 
-        let res = BN128Add::<Byzantium>::run(&input, 500, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let res = BN128Add::<Byzantium>::run(&input, 499, &new_context());
+        test_vectors::check_outputs::<BN128Add<Byzantium>>("bn256Add.json");
+        test_vectors::check_failures::<BN128Add<Byzantium>>("fail-bn256Add.json");
+        assert_eq!(BN128Add::<Byzantium>::required_gas(&[]).unwrap(), 500);
+        let res = BN128Add::<Byzantium>::run(&[0u8; 128], 499, &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
-
-        // no input test
-        let input = [0u8; 0];
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = BN128Add::<Byzantium>::run(&input, 500, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111",
-        )
-        .unwrap();
-
-        let res = BN128Add::<Byzantium>::run(&input, 500, &new_context());
-        assert!(matches!(
-            res,
-            Err(ExitError::Other(Borrowed("invalid curve point")))
-        ));
     }
 
     #[test]
     fn test_alt_bn128_mul() {
-        let input = hex::decode(
-            "\
-            2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7\
-            21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204\
-            00000000000000000000000000000000000000000000000011138ce750fa15c2",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c\
-            031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc",
-        )
-        .unwrap();
+        test_vectors::check_vectors::<BN128Mul<Istanbul>>("bn256ScalarMul.json");
+        test_vectors::check_failures::<BN128Mul<Istanbul>>("fail-bn256ScalarMul.json");
 
-        let res = BN128Mul::<Byzantium>::run(&input, 40_000, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0200000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let res = BN128Mul::<Byzantium>::run(&input, 39_999, &new_context());
+        test_vectors::check_outputs::<BN128Mul<Byzantium>>("bn256ScalarMul.json");
+        test_vectors::check_failures::<BN128Mul<Byzantium>>("fail-bn256ScalarMul.json");
+        assert_eq!(BN128Mul::<Byzantium>::required_gas(&[]).unwrap(), 40_000);
+        let res = BN128Mul::<Byzantium>::run(&[0u8; 96], 39_999, &new_context());
         assert!(matches!(res, Err(ExitError::OutOfGas)));
-
-        // zero multiplication test
-        let input = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0200000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = BN128Mul::<Byzantium>::run(&input, 40_000, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // no input test
-        let input = [0u8; 0];
-        let expected = hex::decode(
-            "\
-            0000000000000000000000000000000000000000000000000000000000000000\
-            0000000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = BN128Mul::<Byzantium>::run(&input, 40_000, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            0f00000000000000000000000000000000000000000000000000000000000000",
-        )
-        .unwrap();
-
-        let res = BN128Mul::<Byzantium>::run(&input, 40_000, &new_context());
-        assert!(matches!(
-            res,
-            Err(ExitError::Other(Borrowed("invalid curve point")))
-        ));
     }
 
     #[test]
     fn test_alt_bn128_pair() {
-        let input = hex::decode(
-            "\
-            1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
-            3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
-            209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
-            04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
-            2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
-            120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
-            111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
-            2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
-            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
-            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
-            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
-            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
-        )
-        .unwrap();
-        let expected =
-            hex::decode("0000000000000000000000000000000000000000000000000000000000000001")
-                .unwrap();
+        test_vectors::check_vectors::<BN128Pair<Istanbul>>("bn256Pairing.json");
+        test_vectors::check_failures::<BN128Pair<Istanbul>>("fail-bn256Pairing.json");
+
+        test_vectors::check_outputs::<BN128Pair<Byzantium>>("bn256Pairing.json");
+        test_vectors::check_failures::<BN128Pair<Byzantium>>("fail-bn256Pairing.json");
+        assert_eq!(
+            BN128Pair::<Byzantium>::required_gas(&[0u8; 384]).unwrap(),
+            260_000
+        );
+        let res = BN128Pair::<Byzantium>::run(&[0u8; 384], 259_999, &new_context());
+        assert!(matches!(res, Err(ExitError::OutOfGas)));
+    }
 
-        let res = BN128Pair::<Byzantium>::run(&input, 260_000, &new_context())
+    /// The alt_bn128 base field modulus.
+    const FIELD_MODULUS: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+    /// The G2 generator, encoded as `x_imag, x_real, y_imag, y_real`.
+    const G2_GENERATOR: &str = "\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    /// Returns `scalar * G1`, where G1 is the generator `(1, 2)`.
+    fn g1_mul(scalar: u128) -> Vec<u8> {
+        let mut input = [0u8; 96];
+        input[31] = 1;
+        input[63] = 2;
+        input[80..96].copy_from_slice(&scalar.to_be_bytes());
+        BN128Mul::<Istanbul>::run(&input, 6_000, &new_context())
             .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // out of gas test
-        let input = hex::decode(
-            "\
-            1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
-            3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
-            209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
-            04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
-            2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
-            120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
-            111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
-            2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
-            198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
-            1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
-            090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
-            12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
-        )
-        .unwrap();
-        let res = BN128Pair::<Byzantium>::run(&input, 259_999, &new_context());
-        assert!(matches!(res, Err(ExitError::OutOfGas)));
+            .output
+    }
 
-        // no input test
-        let input = [0u8; 0];
-        let expected =
-            hex::decode("0000000000000000000000000000000000000000000000000000000000000001")
-                .unwrap();
+    /// Negates a G1 point by replacing `y` with `p - y`.
+    fn g1_neg(point: &[u8]) -> Vec<u8> {
+        let p = U256::from_big_endian(&hex::decode(FIELD_MODULUS).unwrap());
+        let y = U256::from_big_endian(&point[32..64]);
+        let mut negated = point.to_vec();
+        (p - y).to_big_endian(&mut negated[32..64]);
+        negated
+    }
 
-        let res = BN128Pair::<Byzantium>::run(&input, 260_000, &new_context())
-            .unwrap()
-            .1;
-        assert_eq!(res, expected);
-
-        // point not on curve fail
-        let input = hex::decode(
-            "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111",
-        )
-        .unwrap();
-
-        let res = BN128Pair::<Byzantium>::run(&input, 260_000, &new_context());
-        assert!(matches!(
-            res,
-            Err(ExitError::Other(Borrowed(
-                "invalid `a` argument, not on curve"
-            )))
-        ));
-
-        // invalid input length
-        let input = hex::decode(
-            "\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            1111111111111111111111111111111111111111111111111111111111111111\
-            111111111111111111111111111111\
-        ",
-        )
-        .unwrap();
+    proptest! {
+        #[test]
+        fn test_alt_bn128_add_matches_mul(scalar in 1..u128::MAX / 2) {
+            let point = g1_mul(scalar);
+            let input = [point.clone(), point.clone()].concat();
+            let doubled = BN128Add::<Istanbul>::run(&input, 150, &new_context())
+                .unwrap()
+                .output;
+            prop_assert_eq!(doubled, g1_mul(scalar * 2));
+
+            let sum_with_zero = BN128Add::<Istanbul>::run(&point, 150, &new_context())
+                .unwrap()
+                .output;
+            prop_assert_eq!(sum_with_zero, point);
+        }
 
-        let res = BN128Pair::<Byzantium>::run(&input, 260_000, &new_context());
-        assert!(matches!(
-            res,
-            Err(ExitError::Other(Borrowed(
-                "input length invalid, must be multiple of 192",
-            )))
-        ));
+        #[test]
+        fn test_alt_bn128_arbitrary_input(input in prop::collection::vec(any::<u8>(), 0..160)) {
+            // Must not panic, whatever the outcome.
+            let _ = BN128Add::<Istanbul>::run(&input, 150, &new_context());
+            let _ = BN128Mul::<Istanbul>::run(&input, 6_000, &new_context());
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_alt_bn128_pair_cancels(scalar in 1..u128::MAX) {
+            let g2 = hex::decode(G2_GENERATOR).unwrap();
+            let point = g1_mul(scalar);
+            let input = [point.clone(), g2.clone(), g1_neg(&point), g2].concat();
+
+            let gas = BN128Pair::<Istanbul>::required_gas(&input).unwrap();
+            let res = BN128Pair::<Istanbul>::run(&input, gas, &new_context())
+                .unwrap()
+                .output;
+            let mut expected = [0u8; 32];
+            expected[31] = 1;
+            prop_assert_eq!(res, expected.to_vec());
+        }
+
+        #[test]
+        fn test_alt_bn128_pair_arbitrary(input in prop::collection::vec(any::<u8>(), 0..400)) {
+            // Must not panic, whatever the outcome.
+            let _ = BN128Pair::<Istanbul>::run(&input, u64::MAX, &new_context());
+        }
     }
 }
@@ -0,0 +1,180 @@
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
+use crate::prelude::*;
+use core::convert::TryFrom;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use evm::{Context, ExitError};
+
+/// Ed25519 verification costs.
+///
+/// The verification runs in-wasm, so its EVM gas is the NEAR gas it burns,
+/// converted at the NEAR gas per EVM gas of `ecrecover`, the Ethereum
+/// precompile doing the most similar work. `benches/ed25519.rs` measures the
+/// NEAR gas figures below. They are estimates until it is run on a release
+/// build, and `precompile_ed25519` stays out of `all_precompiles` until then.
+mod costs {
+    /// The EVM gas of an `ecrecover` call.
+    const ECRECOVER_GAS: u64 = 3_000;
+
+    /// The NEAR gas an `ecrecover` call burns.
+    const ECRECOVER_NEAR_GAS: u64 = 6_000_000_000_000;
+
+    const NEAR_GAS_PER_EVM_GAS: u64 = ECRECOVER_NEAR_GAS / ECRECOVER_GAS;
+
+    /// The NEAR gas a verification of an empty message burns.
+    const ED25519_VERIFY_NEAR_GAS: u64 = 9_000_000_000_000;
+
+    /// The NEAR gas a verification burns per word of the message, which gets
+    /// hashed with SHA-512.
+    const ED25519_VERIFY_NEAR_GAS_PER_WORD: u64 = 30_000_000_000;
+
+    /// The base cost of a signature verification.
+    pub(super) const ED25519_VERIFY_BASE: u64 = ED25519_VERIFY_NEAR_GAS / NEAR_GAS_PER_EVM_GAS;
+
+    /// The cost per word of the signed message.
+    pub(super) const ED25519_VERIFY_PER_WORD: u64 =
+        ED25519_VERIFY_NEAR_GAS_PER_WORD / NEAR_GAS_PER_EVM_GAS;
+}
+
+mod consts {
+    pub(super) const PUBLIC_KEY_LEN: usize = 32;
+    pub(super) const SIGNATURE_LEN: usize = 64;
+    pub(super) const WORD_LEN: u64 = 32;
+}
+
+pub(super) struct Ed25519Verify;
+
+impl Ed25519Verify {
+    /// `keccak256("nearEd25519Verify")[12..]`
+    pub(super) const ADDRESS: Address = H160([
+        0xd4, 0x38, 0x26, 0xdb, 0xa7, 0xd1, 0xfb, 0xf4, 0x0c, 0x43, 0xd0, 0x2a, 0xbb, 0x6a, 0xbc,
+        0x5c, 0xbf, 0xac, 0xf2, 0x1d,
+    ]);
+}
+
+impl Precompile for Ed25519Verify {
+    fn required_gas(input: &[u8]) -> Result<u64, ExitError> {
+        let message_len = input
+            .len()
+            .saturating_sub(consts::PUBLIC_KEY_LEN + consts::SIGNATURE_LEN)
+            as u64;
+        Ok(
+            (message_len + consts::WORD_LEN - 1) / consts::WORD_LEN
+                * costs::ED25519_VERIFY_PER_WORD
+                + costs::ED25519_VERIFY_BASE,
+        )
+    }
+
+    /// Verifies an ed25519 signature, as used by NEAR access keys.
+    ///
+    /// The input is the 32-byte public key, the 64-byte signature and then the
+    /// signed message, with no padding (`abi.encodePacked(key, sig, message)`).
+    /// Returns a 32-byte word that is 1 if the signature is valid and 0 if it
+    /// is not, including when the key or signature cannot be parsed.
+    fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
+            return Err(ExitError::OutOfGas);
+        }
+        if input.len() < consts::PUBLIC_KEY_LEN + consts::SIGNATURE_LEN {
+            return Err(ExitError::Other(Borrowed(
+                "input too short, must be at least 96 bytes",
+            )));
+        }
+
+        let (public_key, rest) = input.split_at(consts::PUBLIC_KEY_LEN);
+        let (signature, message) = rest.split_at(consts::SIGNATURE_LEN);
+        let valid = match (
+            PublicKey::from_bytes(public_key),
+            Signature::try_from(signature),
+        ) {
+            (Ok(public_key), Ok(signature)) => public_key.verify(message, &signature).is_ok(),
+            _ => false,
+        };
+
+        let mut output = vec![0u8; 32];
+        output[31] = valid as u8;
+        Ok(PrecompileOutput::without_logs(cost, output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
+
+    fn new_context() -> Context {
+        Context {
+            address: Default::default(),
+            caller: Default::default(),
+            apparent_value: Default::default(),
+        }
+    }
+
+    fn sign(message: &[u8]) -> Vec<u8> {
+        let signer = InMemorySigner::from_seed("test.near", KeyType::ED25519, "test");
+        let public_key = match signer.public_key.clone() {
+            PublicKey::ED25519(public_key) => public_key.0,
+            PublicKey::SECP256K1(_) => unreachable!(),
+        };
+        let signature = match signer.sign(message) {
+            Signature::ED25519(signature) => signature.to_bytes(),
+            Signature::SECP256K1(_) => unreachable!(),
+        };
+
+        let mut input = Vec::new();
+        input.extend_from_slice(&public_key);
+        input.extend_from_slice(&signature);
+        input.extend_from_slice(message);
+        input
+    }
+
+    fn verify(input: &[u8]) -> Vec<u8> {
+        Ed25519Verify::run(input, u64::MAX, &new_context())
+            .unwrap()
+            .output
+    }
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            Ed25519Verify::ADDRESS.as_bytes(),
+            &crate::types::keccak(b"nearEd25519Verify")[12..]
+        );
+    }
+
+    #[test]
+    fn test_ed25519_verify() {
+        let message = b"hello from test.near";
+        let mut input = sign(message);
+        assert_eq!(verify(&input)[31], 1);
+
+        // Tampered message.
+        let last = input.len() - 1;
+        input[last] ^= 1;
+        assert_eq!(verify(&input), vec![0u8; 32]);
+
+        // Tampered signature.
+        let mut input = sign(message);
+        input[40] ^= 1;
+        assert_eq!(verify(&input), vec![0u8; 32]);
+
+        // Empty message.
+        assert_eq!(verify(&sign(b""))[31], 1);
+
+        let res = Ed25519Verify::run(&[0u8; 95], u64::MAX, &new_context());
+        assert!(matches!(
+            res,
+            Err(ExitError::Other(Borrowed(
+                "input too short, must be at least 96 bytes"
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_ed25519_verify_gas() {
+        let input = sign(&[0u8; 33]);
+        assert_eq!(Ed25519Verify::required_gas(&input).unwrap(), 4_530);
+        let res = Ed25519Verify::run(&input, 4_529, &new_context());
+        assert!(matches!(res, Err(ExitError::OutOfGas)));
+    }
+}
@@ -1,5 +1,5 @@
-use crate::precompiles::{Precompile, PrecompileResult};
-use evm::{Context, ExitError, ExitSucceed};
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
+use evm::{Context, ExitError};
 
 mod costs {
     pub(super) const SHA256_BASE: u64 = 60;
@@ -36,12 +36,13 @@ impl Precompile for SHA256 {
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
         use sha2::Digest;
 
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             return Err(ExitError::OutOfGas);
         }
 
         let hash = sha2::Sha256::digest(input);
-        Ok((ExitSucceed::Returned, hash.to_vec(), 0))
+        Ok(PrecompileOutput::without_logs(cost, hash.to_vec()))
     }
 
     /// See: https://ethereum.github.io/yellowpaper/paper.pdf
@@ -51,13 +52,13 @@ impl Precompile for SHA256 {
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
         use crate::sdk;
 
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Ok((
-                ExitSucceed::Returned,
+            Ok(PrecompileOutput::without_logs(
+                cost,
                 sdk::sha256(input).as_bytes().to_vec(),
-                0,
             ))
         }
     }
@@ -81,7 +82,8 @@ impl Precompile for RIPEMD160 {
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
         use ripemd160::Digest;
 
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
             let hash = ripemd160::Ripemd160::digest(input);
@@ -89,7 +91,7 @@ impl Precompile for RIPEMD160 {
             // the evm works with 32-byte words.
             let mut result = [0u8; 32];
             result[12..].copy_from_slice(&hash);
-            Ok((ExitSucceed::Returned, result.to_vec(), 0))
+            Ok(PrecompileOutput::without_logs(cost, result.to_vec()))
         }
     }
 }
@@ -97,6 +99,8 @@ impl Precompile for RIPEMD160 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
+    use proptest::prelude::*;
 
     fn new_context() -> Context {
         Context {
@@ -113,7 +117,7 @@ mod tests {
             hex::decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
                 .unwrap();
 
-        let res = SHA256::run(input, 60, &new_context()).unwrap().1;
+        let res = SHA256::run(input, 60, &new_context()).unwrap().output;
         assert_eq!(res, expected);
     }
 
@@ -124,7 +128,42 @@ mod tests {
             hex::decode("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31")
                 .unwrap();
 
-        let res = RIPEMD160::run(input, 600, &new_context()).unwrap().1;
+        let res = RIPEMD160::run(input, 600, &new_context()).unwrap().output;
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn test_sha256_vectors() {
+        test_vectors::check_vectors::<SHA256>("sha256.json");
+    }
+
+    #[test]
+    fn test_ripemd160_vectors() {
+        test_vectors::check_vectors::<RIPEMD160>("ripemd160.json");
+    }
+
+    // The outputs are checked against published digests above, these only
+    // check the gas schedule and the shape of the output on random inputs.
+    proptest! {
+        #[test]
+        fn test_sha256_gas(input in prop::collection::vec(any::<u8>(), 0..512)) {
+            let gas = SHA256::required_gas(&input).unwrap();
+            prop_assert_eq!(gas, 60 + 12 * ((input.len() as u64 + 31) / 32));
+
+            let res = SHA256::run(&input, gas, &new_context()).unwrap();
+            prop_assert_eq!(res.cost, gas);
+            prop_assert_eq!(res.output.len(), 32);
+        }
+
+        #[test]
+        fn test_ripemd160_gas(input in prop::collection::vec(any::<u8>(), 0..512)) {
+            let gas = RIPEMD160::required_gas(&input).unwrap();
+            prop_assert_eq!(gas, 600 + 12 * ((input.len() as u64 + 31) / 32));
+
+            let res = RIPEMD160::run(&input, gas, &new_context()).unwrap();
+            prop_assert_eq!(res.cost, gas);
+            prop_assert_eq!(res.output.len(), 32);
+            prop_assert_eq!(&res.output[..12], &[0u8; 12][..]);
+        }
+    }
 }
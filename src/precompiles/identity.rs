@@ -1,5 +1,5 @@
-use crate::precompiles::{Precompile, PrecompileResult};
-use evm::{Context, ExitError, ExitSucceed};
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
+use evm::{Context, ExitError};
 
 /// Identity precompile costs.
 mod costs {
@@ -31,10 +31,11 @@ impl Precompile for Identity {
     /// See: https://ethereum.github.io/yellowpaper/paper.pdf
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000004
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             Err(ExitError::OutOfGas)
         } else {
-            Ok((ExitSucceed::Returned, input.to_vec(), 0))
+            Ok(PrecompileOutput::without_logs(cost, input.to_vec()))
         }
     }
 }
@@ -42,7 +43,9 @@ impl Precompile for Identity {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
     use evm::ExitError;
+    use proptest::prelude::*;
 
     fn new_context() -> Context {
         Context {
@@ -57,11 +60,13 @@ mod tests {
         let input = [0u8, 1, 2, 3];
 
         let expected = input[0..2].to_vec();
-        let res = Identity::run(&input[0..2], 18, &new_context()).unwrap().1;
+        let res = Identity::run(&input[0..2], 18, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         let expected = input.to_vec();
-        let res = Identity::run(&input, 18, &new_context()).unwrap().1;
+        let res = Identity::run(&input, 18, &new_context()).unwrap().output;
         assert_eq!(res, expected);
 
         // gas fail
@@ -74,7 +79,26 @@ mod tests {
             0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
             24, 25, 26, 27, 28, 29, 30, 31, 32,
         ];
-        let res = Identity::run(&input, 21, &new_context()).unwrap().1;
+        let res = Identity::run(&input, 21, &new_context()).unwrap().output;
         assert_eq!(res, input.to_vec());
     }
+
+    #[test]
+    fn test_identity_vectors() {
+        test_vectors::check_vectors::<Identity>("identity.json");
+    }
+
+    proptest! {
+        #[test]
+        fn test_identity_returns_input(input in prop::collection::vec(any::<u8>(), 0..512)) {
+            let gas = Identity::required_gas(&input).unwrap();
+            prop_assert_eq!(gas, 15 + 3 * ((input.len() as u64 + 31) / 32));
+
+            let res = Identity::run(&input, gas, &new_context()).unwrap().output;
+            prop_assert_eq!(&res, &input);
+
+            let res = Identity::run(&input, gas - 1, &new_context());
+            prop_assert!(matches!(res, Err(ExitError::OutOfGas)));
+        }
+    }
 }
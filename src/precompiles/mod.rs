@@ -1,22 +1,81 @@
+#[cfg(feature = "precompile_blake2")]
 mod blake2;
+#[cfg(feature = "precompile_bn128")]
 mod bn128;
+#[cfg(feature = "precompile_ed25519")]
+mod ed25519;
 mod hash;
 mod identity;
+#[cfg(feature = "precompile_modexp")]
 mod modexp;
+#[cfg(feature = "contract")]
+mod near_block;
+#[cfg(feature = "precompile_p256")]
+mod p256;
 mod secp256k1;
+#[cfg(test)]
+mod test_vectors;
 
+#[cfg(feature = "precompile_blake2")]
 use crate::precompiles::blake2::Blake2F;
+#[cfg(feature = "precompile_bn128")]
 use crate::precompiles::bn128::{BN128Add, BN128Mul, BN128Pair};
+#[cfg(feature = "precompile_ed25519")]
+use crate::precompiles::ed25519::Ed25519Verify;
 use crate::precompiles::hash::{RIPEMD160, SHA256};
 use crate::precompiles::identity::Identity;
+#[cfg(feature = "precompile_modexp")]
 use crate::precompiles::modexp::ModExp;
+#[cfg(feature = "contract")]
+use crate::precompiles::near_block::NearBlockInfo;
+#[cfg(feature = "precompile_p256")]
+use crate::precompiles::p256::P256Verify;
 pub(crate) use crate::precompiles::secp256k1::ecrecover;
 use crate::precompiles::secp256k1::ECRecover;
-use crate::prelude::{Address, Vec};
+use crate::prelude::{Address, Borrowed, Vec};
+use evm::backend::Log;
 use evm::{Context, ExitError, ExitSucceed};
 
+/// The output of a successful precompile call.
+#[derive(Debug)]
+struct PrecompileOutput {
+    /// The gas used by the call, which the executor charges to the caller.
+    cost: u64,
+    output: Vec<u8>,
+    /// Logs emitted by the call, to go into the transaction's logs.
+    logs: Vec<Log>,
+}
+
+impl PrecompileOutput {
+    fn without_logs(cost: u64, output: Vec<u8>) -> Self {
+        Self {
+            cost,
+            output,
+            logs: Vec::new(),
+        }
+    }
+}
+
 /// A precompile operation result.
-type PrecompileResult = Result<(ExitSucceed, Vec<u8>, u64), ExitError>;
+type PrecompileResult = Result<PrecompileOutput, ExitError>;
+
+/// A precompile operation result, in the form the `evm` executor takes it.
+pub type EvmPrecompileResult = Result<(ExitSucceed, Vec<u8>, u64), ExitError>;
+
+/// Converts a precompile result for the executor.
+///
+/// The executor takes no logs from precompiles, that needs a change in our
+/// sputnikvm fork, so a precompile emitting logs fails rather than have them
+/// silently dropped.
+fn into_evm_result(result: PrecompileResult) -> EvmPrecompileResult {
+    let output = result?;
+    if !output.logs.is_empty() {
+        return Err(ExitError::Other(Borrowed(
+            "precompile logs are not supported",
+        )));
+    }
+    Ok((ExitSucceed::Returned, output.output, output.cost))
+}
 
 /// A precompiled function for use in the EVM.
 trait Precompile {
@@ -33,13 +92,13 @@ trait HardFork {}
 /// Homestead hard fork marker.
 struct Homestead;
 
-/// Homestead hard fork marker.
+/// Byzantium hard fork marker.
 struct Byzantium;
 
-/// Homestead hard fork marker.
+/// Istanbul hard fork marker.
 struct Istanbul;
 
-/// Homestead hard fork marker.
+/// Berlin hard fork marker.
 struct Berlin;
 
 impl HardFork for Homestead {}
@@ -50,6 +109,274 @@ impl HardFork for Istanbul {}
 
 impl HardFork for Berlin {}
 
+/// Returns the last byte of a precompile address, or `None` for addresses
+/// outside of the `0x00..01`-`0x00..ff` range reserved for precompiles.
+fn precompile_index(address: &Address) -> Option<u8> {
+    let (prefix, index) = address.0.split_at(19);
+    if prefix == [0u8; 19] {
+        Some(index[0])
+    } else {
+        None
+    }
+}
+
+/// Slot of `P256Verify` in the dispatch tables, which hold the Ethereum
+/// precompiles at their index and the others after them.
+const P256_VERIFY_SLOT: usize = 10;
+
+/// Slot of `nearEd25519Verify` in the dispatch tables.
+const ED25519_VERIFY_SLOT: usize = 11;
+
+/// Slot of `nearBlockInfo` in the dispatch tables.
+const NEAR_BLOCK_INFO_SLOT: usize = 12;
+
+const SLOTS: usize = 13;
+
+/// Returns the dispatch table slot for `address`, or `None` if no hard fork
+/// has a precompile there.
+///
+/// Every call to a regular contract goes through this check, so it is kept
+/// to a few comparisons.
+fn slot(address: &Address) -> Option<usize> {
+    match precompile_index(address) {
+        Some(index) if index != 0 && (index as usize) < P256_VERIFY_SLOT => Some(index as usize),
+        Some(_) => None,
+        None => named_slot(address),
+    }
+}
+
+/// Slots of the precompiles outside of the single-byte range: the rollup
+/// ones, which RIPs number from 0x100, and the Aurora-specific ones, which live
+/// at the last 20 bytes of the keccak256 hash of their name so that they
+/// cannot clash with precompiles added by future hard forks.
+fn named_slot(address: &Address) -> Option<usize> {
+    match *address {
+        #[cfg(feature = "precompile_p256")]
+        a if a == P256Verify::ADDRESS => Some(P256_VERIFY_SLOT),
+        #[cfg(feature = "precompile_ed25519")]
+        a if a == Ed25519Verify::ADDRESS => Some(ED25519_VERIFY_SLOT),
+        #[cfg(feature = "contract")]
+        a if a == NearBlockInfo::ADDRESS => Some(NEAR_BLOCK_INFO_SLOT),
+        _ => None,
+    }
+}
+
+/// Returns whether any hard fork may have a precompile at the address.
+pub fn is_precompile_address(address: &Address) -> bool {
+    slot(address).is_some()
+}
+
+/// Bit of `nearEd25519Verify` in masks of paused precompiles.
+pub const ED25519_VERIFY_FLAG: u32 = 1 << 0;
+
+/// Bit of `nearBlockInfo` in masks of paused precompiles.
+pub const NEAR_BLOCK_INFO_FLAG: u32 = 1 << 1;
+
+/// A precompile function, taking the input, the gas limit and the context.
+type PrecompileFn = fn(&[u8], u64, &Context) -> PrecompileResult;
+
+/// The precompiles of a hard fork, by slot.
+type Table = [Option<PrecompileFn>; SLOTS];
+
+/// `Some(run)` if `feature` is enabled, `None` otherwise.
+macro_rules! optional {
+    ($feature:tt, $run:expr) => {{
+        #[cfg(feature = $feature)]
+        let run: Option<PrecompileFn> = Some($run);
+        #[cfg(not(feature = $feature))]
+        let run: Option<PrecompileFn> = None;
+        run
+    }};
+}
+
+static HOMESTEAD: Table = [
+    None,
+    Some(ECRecover::run),
+    Some(SHA256::run),
+    Some(RIPEMD160::run),
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+];
+
+static BYZANTIUM: Table = [
+    None,
+    Some(ECRecover::run),
+    Some(SHA256::run),
+    Some(RIPEMD160::run),
+    Some(Identity::run),
+    optional!("precompile_modexp", ModExp::<Byzantium>::run),
+    optional!("precompile_bn128", BN128Add::<Byzantium>::run),
+    optional!("precompile_bn128", BN128Mul::<Byzantium>::run),
+    optional!("precompile_bn128", BN128Pair::<Byzantium>::run),
+    None,
+    optional!("precompile_p256", P256Verify::run),
+    optional!("precompile_ed25519", Ed25519Verify::run),
+    optional!("contract", NearBlockInfo::run),
+];
+
+static ISTANBUL: Table = [
+    None,
+    Some(ECRecover::run),
+    Some(SHA256::run),
+    Some(RIPEMD160::run),
+    Some(Identity::run),
+    optional!("precompile_modexp", ModExp::<Byzantium>::run),
+    optional!("precompile_bn128", BN128Add::<Istanbul>::run),
+    optional!("precompile_bn128", BN128Mul::<Istanbul>::run),
+    optional!("precompile_bn128", BN128Pair::<Istanbul>::run),
+    optional!("precompile_blake2", Blake2F::run),
+    optional!("precompile_p256", P256Verify::run),
+    optional!("precompile_ed25519", Ed25519Verify::run),
+    optional!("contract", NearBlockInfo::run),
+];
+
+static BERLIN: Table = [
+    None,
+    Some(ECRecover::run),
+    Some(SHA256::run),
+    Some(RIPEMD160::run),
+    Some(Identity::run),
+    optional!("precompile_modexp", ModExp::<Berlin>::run),
+    optional!("precompile_bn128", BN128Add::<Istanbul>::run),
+    optional!("precompile_bn128", BN128Mul::<Istanbul>::run),
+    optional!("precompile_bn128", BN128Pair::<Istanbul>::run),
+    optional!("precompile_blake2", Blake2F::run),
+    optional!("precompile_p256", P256Verify::run),
+    optional!("precompile_ed25519", Ed25519Verify::run),
+    optional!("contract", NearBlockInfo::run),
+];
+
+/// The precompiles of a hard fork, less the disabled ones.
+///
+/// From Byzantium on, the sets also include the rollup and Aurora-specific
+/// precompiles. Removing a precompile disables it, its address then behaves
+/// like any other account without code.
+///
+/// A set is a static table and two masks, so it is cheap to build and copy;
+/// the engine builds its set once, when it is created.
+#[derive(Clone, Copy)]
+pub struct PrecompileSet {
+    table: &'static Table,
+    /// Bits of the slots of disabled precompiles.
+    disabled: u32,
+    /// Bits of the slots of paused precompiles.
+    paused: u32,
+}
+
+impl PrecompileSet {
+    fn new(table: &'static Table) -> Self {
+        Self {
+            table,
+            disabled: 0,
+            paused: 0,
+        }
+    }
+
+    pub fn homestead() -> Self {
+        Self::new(&HOMESTEAD)
+    }
+
+    pub fn byzantium() -> Self {
+        Self::new(&BYZANTIUM)
+    }
+
+    pub fn istanbul() -> Self {
+        Self::new(&ISTANBUL)
+    }
+
+    pub fn berlin() -> Self {
+        Self::new(&BERLIN)
+    }
+
+    /// Returns the slot of the precompile at `address`, `None` if there is
+    /// none or it is disabled.
+    fn find(&self, address: &Address) -> Option<usize> {
+        let slot = slot(address)?;
+        if self.disabled & (1 << slot) != 0 || self.table[slot].is_none() {
+            return None;
+        }
+        Some(slot)
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.find(address).is_some()
+    }
+
+    /// Disables the precompile at `address`, returning whether there was one.
+    pub fn remove(&mut self, address: &Address) -> bool {
+        match self.find(address) {
+            Some(slot) => {
+                self.disabled |= 1 << slot;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pauses the precompile at `address`, so that calls to it fail with
+    /// `ERR_PRECOMPILE_PAUSED`, unlike calls to a removed one. Returns whether
+    /// there was one.
+    pub fn pause(&mut self, address: &Address) -> bool {
+        match self.find(address) {
+            Some(slot) => {
+                self.paused |= 1 << slot;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns whether the precompile at `address` is paused.
+    pub fn is_paused(&self, address: &Address) -> bool {
+        match self.find(address) {
+            Some(slot) => self.paused & (1 << slot) != 0,
+            None => false,
+        }
+    }
+
+    /// Pauses the Aurora-specific precompiles whose flags are set in `mask`.
+    pub fn pause_aurora(&mut self, mask: u32) {
+        if mask & ED25519_VERIFY_FLAG != 0 {
+            self.paused |= 1 << ED25519_VERIFY_SLOT;
+        }
+        if mask & NEAR_BLOCK_INFO_FLAG != 0 {
+            self.paused |= 1 << NEAR_BLOCK_INFO_SLOT;
+        }
+    }
+
+    /// Runs the precompile at `address`, `None` if there is none.
+    ///
+    /// Calls to a paused precompile fail with `ERR_PRECOMPILE_PAUSED`. The
+    /// precompile hook of our sputnikvm fork cannot revert, so contracts
+    /// calling it see a failed call without a reason.
+    pub fn run(
+        &self,
+        address: Address,
+        input: &[u8],
+        target_gas: Option<u64>,
+        context: &Context,
+    ) -> Option<EvmPrecompileResult> {
+        let slot = self.find(&address)?;
+        if self.paused & (1 << slot) != 0 {
+            return Some(Err(ExitError::Other(Borrowed("ERR_PRECOMPILE_PAUSED"))));
+        }
+        let run = self.table[slot]?;
+        let target_gas = match target_gas {
+            Some(t) => t,
+            None => return Some(Err(ExitError::OutOfGas)),
+        };
+
+        Some(into_evm_result(run(input, target_gas, context)))
+    }
+}
+
 /// No precompiles, returns `None`.
 #[allow(dead_code)]
 pub fn no_precompiles(
@@ -57,7 +384,7 @@ pub fn no_precompiles(
     _input: &[u8],
     _target_gas: Option<u64>,
     _context: &Context,
-) -> Option<PrecompileResult> {
+) -> Option<EvmPrecompileResult> {
     None // no precompiles supported
 }
 
@@ -68,19 +395,8 @@ pub fn homestead_precompiles(
     input: &[u8],
     target_gas: Option<u64>,
     context: &Context,
-) -> Option<PrecompileResult> {
-    let target_gas = match target_gas {
-        Some(t) => t,
-        None => return Some(PrecompileResult::Err(ExitError::OutOfGas)),
-    };
-
-    match address.to_low_u64_be() {
-        1 => Some(ECRecover::run(input, target_gas, context)),
-        2 => Some(SHA256::run(input, target_gas, context)),
-        3 => Some(RIPEMD160::run(input, target_gas, context)),
-        // 4 => Some(identity::identity(input, target_gas)),
-        _ => None,
-    }
+) -> Option<EvmPrecompileResult> {
+    PrecompileSet::homestead().run(address, input, target_gas, context)
 }
 
 /// Matches the address given to Byzantium precompiles.
@@ -90,23 +406,8 @@ pub fn byzantium_precompiles(
     input: &[u8],
     target_gas: Option<u64>,
     context: &Context,
-) -> Option<PrecompileResult> {
-    let target_gas = match target_gas {
-        Some(t) => t,
-        None => return Some(PrecompileResult::Err(ExitError::OutOfGas)),
-    };
-
-    match address.to_low_u64_be() {
-        1 => Some(ECRecover::run(input, target_gas, context)),
-        2 => Some(SHA256::run(input, target_gas, context)),
-        3 => Some(RIPEMD160::run(input, target_gas, context)),
-        4 => Some(Identity::run(input, target_gas, context)),
-        5 => Some(ModExp::<Byzantium>::run(input, target_gas, context)),
-        6 => Some(BN128Add::<Byzantium>::run(input, target_gas, context)),
-        7 => Some(BN128Mul::<Byzantium>::run(input, target_gas, context)),
-        8 => Some(BN128Pair::<Byzantium>::run(input, target_gas, context)),
-        _ => None,
-    }
+) -> Option<EvmPrecompileResult> {
+    PrecompileSet::byzantium().run(address, input, target_gas, context)
 }
 
 /// Matches the address given to Istanbul precompiles.
@@ -116,25 +417,8 @@ pub fn istanbul_precompiles(
     input: &[u8],
     target_gas: Option<u64>,
     context: &Context,
-) -> Option<PrecompileResult> {
-    let target_gas = match target_gas {
-        Some(t) => t,
-        None => return Some(PrecompileResult::Err(ExitError::OutOfGas)),
-    };
-
-    match address.to_low_u64_be() {
-        1 => Some(ECRecover::run(input, target_gas, context)),
-        2 => Some(SHA256::run(input, target_gas, context)),
-        3 => Some(RIPEMD160::run(input, target_gas, context)),
-        4 => Some(Identity::run(input, target_gas, context)),
-        5 => Some(ModExp::<Byzantium>::run(input, target_gas, context)),
-        6 => Some(BN128Add::<Istanbul>::run(input, target_gas, context)),
-        7 => Some(BN128Mul::<Istanbul>::run(input, target_gas, context)),
-        8 => Some(BN128Pair::<Istanbul>::run(input, target_gas, context)),
-        9 => Some(Blake2F::run(input, target_gas, context)),
-        // Not supported.
-        _ => None,
-    }
+) -> Option<EvmPrecompileResult> {
+    PrecompileSet::istanbul().run(address, input, target_gas, context)
 }
 
 /// Matches the address given to Berlin precompiles.
@@ -144,23 +428,142 @@ pub fn berlin_precompiles(
     input: &[u8],
     target_gas: Option<u64>,
     context: &Context,
-) -> Option<PrecompileResult> {
-    let target_gas = match target_gas {
-        Some(t) => t,
-        None => return Some(PrecompileResult::Err(ExitError::OutOfGas)),
-    };
-
-    match address.to_low_u64_be() {
-        1 => Some(ECRecover::run(input, target_gas, context)),
-        2 => Some(SHA256::run(input, target_gas, context)),
-        3 => Some(RIPEMD160::run(input, target_gas, context)),
-        4 => Some(Identity::run(input, target_gas, context)),
-        5 => Some(ModExp::<Berlin>::run(input, target_gas, context)), // TODO gas changes
-        6 => Some(BN128Add::<Istanbul>::run(input, target_gas, context)),
-        7 => Some(BN128Mul::<Istanbul>::run(input, target_gas, context)),
-        8 => Some(BN128Pair::<Istanbul>::run(input, target_gas, context)),
-        9 => Some(Blake2F::run(input, target_gas, context)),
-        // Not supported.
-        _ => None,
+) -> Option<EvmPrecompileResult> {
+    PrecompileSet::berlin().run(address, input, target_gas, context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::precompiles::test_vectors;
+
+    #[test]
+    fn test_precompile_index() {
+        let mut address = Address::from_low_u64_be(1);
+        assert_eq!(precompile_index(&address), Some(1));
+
+        // Only the last byte may be non-zero.
+        address.0[0] = 1;
+        assert_eq!(precompile_index(&address), None);
+        assert!(!is_precompile_address(&address));
+
+        // No hard fork has a precompile at zero or past 9 in the range.
+        assert!(!is_precompile_address(&Address::zero()));
+        assert!(is_precompile_address(&Address::from_low_u64_be(9)));
+        assert!(!is_precompile_address(&Address::from_low_u64_be(10)));
+        let context = Context {
+            address: Default::default(),
+            caller: Default::default(),
+            apparent_value: Default::default(),
+        };
+        assert!(istanbul_precompiles(address, &[], Some(u64::MAX), &context).is_none());
+    }
+
+    #[cfg(feature = "precompile_ed25519")]
+    #[test]
+    fn test_aurora_precompiles() {
+        let context = Context {
+            address: Default::default(),
+            caller: Default::default(),
+            apparent_value: Default::default(),
+        };
+        let res = istanbul_precompiles(Ed25519Verify::ADDRESS, &[0; 96], Some(u64::MAX), &context);
+        assert_eq!(res.unwrap().unwrap().1, vec![0u8; 32]);
+
+        let res = istanbul_precompiles(Ed25519Verify::ADDRESS, &[0; 96], None, &context);
+        assert!(matches!(res, Some(Err(ExitError::OutOfGas))));
+
+        let mut set = PrecompileSet::istanbul();
+        set.pause_aurora(NEAR_BLOCK_INFO_FLAG);
+        let res = set.run(Ed25519Verify::ADDRESS, &[0; 96], Some(u64::MAX), &context);
+        assert!(res.unwrap().is_ok());
+        assert!(!set.is_paused(&Ed25519Verify::ADDRESS));
+        set.pause_aurora(ED25519_VERIFY_FLAG);
+        assert!(set.is_paused(&Ed25519Verify::ADDRESS));
+        let res = set.run(Ed25519Verify::ADDRESS, &[0; 96], Some(u64::MAX), &context);
+        assert!(matches!(
+            res,
+            Some(Err(ExitError::Other(Borrowed("ERR_PRECOMPILE_PAUSED"))))
+        ));
+    }
+
+    #[cfg(feature = "precompile_p256")]
+    #[test]
+    fn test_rollup_precompiles() {
+        let context = Context {
+            address: Default::default(),
+            caller: Default::default(),
+            apparent_value: Default::default(),
+        };
+        assert!(is_precompile_address(&P256Verify::ADDRESS));
+        assert!(!PrecompileSet::homestead().contains(&P256Verify::ADDRESS));
+        let res = istanbul_precompiles(P256Verify::ADDRESS, &[0; 160], Some(3_450), &context);
+        assert_eq!(
+            res.unwrap().unwrap(),
+            (ExitSucceed::Returned, Vec::new(), 3_450)
+        );
+        let next = Address::from_low_u64_be(0x101);
+        assert!(istanbul_precompiles(next, &[0; 160], Some(u64::MAX), &context).is_none());
+    }
+
+    #[test]
+    fn test_precompile_set() {
+        let context = Context {
+            address: Default::default(),
+            caller: Default::default(),
+            apparent_value: Default::default(),
+        };
+        let identity = Address::from_low_u64_be(4);
+        assert!(!PrecompileSet::homestead().contains(&identity));
+        assert!(PrecompileSet::byzantium().contains(&identity));
+
+        let mut set = PrecompileSet::istanbul();
+        let res = set.run(identity, &[1, 2], Some(u64::MAX), &context);
+        assert_eq!(
+            res.unwrap().unwrap(),
+            (ExitSucceed::Returned, vec![1, 2], 18)
+        );
+
+        assert!(set.remove(&identity));
+        assert!(!set.remove(&identity));
+        assert!(set
+            .run(identity, &[1, 2], Some(u64::MAX), &context)
+            .is_none());
+        assert!(set.run(identity, &[1, 2], None, &context).is_none());
+
+        let sha256 = Address::from_low_u64_be(2);
+        assert!(set.pause(&sha256));
+        assert!(!set.pause(&identity));
+        assert!(set.is_paused(&sha256));
+        assert!(!set.is_paused(&identity));
+        assert!(matches!(
+            set.run(sha256, &[], Some(u64::MAX), &context),
+            Some(Err(ExitError::Other(Borrowed("ERR_PRECOMPILE_PAUSED"))))
+        ));
+    }
+
+    #[test]
+    fn test_into_evm_result() {
+        let output = PrecompileOutput::without_logs(15, vec![1, 2]);
+        let res = into_evm_result(Ok(output)).unwrap();
+        assert_eq!(res, (ExitSucceed::Returned, vec![1, 2], 15));
+
+        let mut output = PrecompileOutput::without_logs(15, Vec::new());
+        output.logs.push(Log {
+            address: Address::zero(),
+            topics: Vec::new(),
+            data: Vec::new(),
+        });
+        assert!(matches!(
+            into_evm_result(Ok(output)),
+            Err(ExitError::Other(Borrowed(
+                "precompile logs are not supported"
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_conformance_corpus() {
+        test_vectors::check_conformance("conformance");
     }
 }
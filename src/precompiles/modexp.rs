@@ -1,39 +1,115 @@
-use crate::precompiles::{Berlin, Byzantium, HardFork, Precompile, PrecompileResult};
-use crate::prelude::{PhantomData, Vec, U256};
-use evm::{Context, ExitError, ExitSucceed};
+use crate::precompiles::{
+    Berlin, Byzantium, HardFork, Precompile, PrecompileOutput, PrecompileResult,
+};
+use crate::prelude::{vec, PhantomData, Vec, U256};
+use evm::{Context, ExitError};
 use num::BigUint;
 
 pub(super) struct ModExp<HF: HardFork>(PhantomData<HF>);
 
-impl ModExp<Byzantium> {
+/// Converts a `U256` into a `usize`, saturating at `usize::MAX`.
+fn saturating_usize(value: U256) -> usize {
+    if value > U256::from(usize::MAX) {
+        usize::MAX
+    } else {
+        value.as_usize()
+    }
+}
+
+/// Returns `len` bytes of the input starting at `offset`. The input is treated as if it were
+/// infinitely right-padded with zeros, as required by EIP-198.
+fn get_data(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    if offset < input.len() {
+        let end = core::cmp::min(input.len(), offset.saturating_add(len));
+        data[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+    data
+}
+
+/// Parses the `base_len`, `exp_len` and `mod_len` header of the input.
+fn parse_lengths(input: &[u8]) -> (U256, U256, U256) {
+    let header = get_data(input, 0, 96);
+    (
+        U256::from(&header[0..32]),
+        U256::from(&header[32..64]),
+        U256::from(&header[64..96]),
+    )
+}
+
+impl<HF: HardFork> ModExp<HF> {
+    /// The adjusted exponent length of EIP-198, roughly the bit length of the exponent.
     fn adj_exp_len(exp_len: U256, base_len: U256, bytes: &[u8]) -> U256 {
-        let mut exp32_bytes = Vec::with_capacity(32);
-        for i in 0..32 {
-            if U256::from(96) + base_len + U256::from(1) >= U256::from(bytes.len()) {
-                exp32_bytes.push(0u8);
-            } else {
-                let base_len_i = base_len.as_usize();
-                let bytes_i = 96 + base_len_i + i;
-                if let Some(byte) = bytes.get(bytes_i) {
-                    exp32_bytes.push(*byte);
-                } else {
-                    // Pad out the data if the byte is empty.
-                    exp32_bytes.push(0u8);
-                }
-            }
-        }
-        let exp32 = U256::from(exp32_bytes.as_slice());
+        // The first (at most) 32 bytes of the exponent, as a big-endian integer.
+        let head_len = core::cmp::min(exp_len, U256::from(32)).as_usize();
+        let exp_offset = saturating_usize(base_len).saturating_add(96);
+        let exp_head = U256::from_big_endian(&get_data(bytes, exp_offset, head_len));
+        let exp_head_bits = exp_head.bits();
 
-        if exp_len <= U256::from(32) && exp32 == U256::zero() {
+        if exp_len <= U256::from(32) && exp_head.is_zero() {
             U256::zero()
         } else if exp_len <= U256::from(32) {
-            U256::from(exp32.bits())
+            U256::from(exp_head_bits - 1)
         } else {
             // else > 32
-            U256::from(8) * (exp_len - U256::from(32)) + U256::from(exp32.bits())
+            let adj = U256::from(8).saturating_mul(exp_len - U256::from(32));
+            if exp_head.is_zero() {
+                adj
+            } else {
+                adj.saturating_add(U256::from(exp_head_bits - 1))
+            }
+        }
+    }
+
+    /// Computes the modexp of an input whose gas has already been checked.
+    fn run_inner(input: &[u8], cost: u64) -> PrecompileResult {
+        let (base_len, exp_len, mod_len) = parse_lengths(input);
+        if mod_len.is_zero() {
+            return Ok(PrecompileOutput::without_logs(cost, Vec::new()));
         }
+
+        // The gas check bounds the lengths whenever the modulus is non-empty.
+        let base_len = saturating_usize(base_len);
+        let exp_len = saturating_usize(exp_len);
+        let mod_len = saturating_usize(mod_len);
+
+        let base_bytes = get_data(input, 96, base_len);
+        let exp_bytes = get_data(input, 96usize.saturating_add(base_len), exp_len);
+        let mod_bytes = get_data(
+            input,
+            96usize.saturating_add(base_len).saturating_add(exp_len),
+            mod_len,
+        );
+
+        let base = BigUint::from_bytes_be(&base_bytes);
+        let exponent = BigUint::from_bytes_be(&exp_bytes);
+        let modulus = BigUint::from_bytes_be(&mod_bytes);
+
+        // `modpow` panics on a zero modulus; EIP-198 defines the result to be zero.
+        if modulus == BigUint::from(0u8) {
+            return Ok(PrecompileOutput::without_logs(cost, vec![0u8; mod_len]));
+        }
+
+        let result = {
+            let computed_result = base.modpow(&exponent, &modulus).to_bytes_be();
+            // The result must be the same length as the input modulus.
+            // To ensure this we pad on the left with zeros.
+            if mod_len > computed_result.len() {
+                let diff = mod_len - computed_result.len();
+                let mut padded_result = Vec::with_capacity(mod_len);
+                padded_result.extend(core::iter::repeat(0).take(diff));
+                padded_result.extend_from_slice(&computed_result);
+                padded_result
+            } else {
+                computed_result
+            }
+        };
+
+        Ok(PrecompileOutput::without_logs(cost, result))
     }
+}
 
+impl ModExp<Byzantium> {
     fn mult_complexity(x: U256) -> Result<U256, ExitError> {
         if x <= U256::from(64) {
             Ok(x * x)
@@ -52,15 +128,13 @@ impl ModExp<Byzantium> {
 
 impl Precompile for ModExp<Byzantium> {
     fn required_gas(input: &[u8]) -> Result<u64, ExitError> {
-        let base_len = U256::from(&input[0..32]);
-        let exp_len = U256::from(&input[32..64]);
-        let mod_len = U256::from(&input[64..96]);
+        let (base_len, exp_len, mod_len) = parse_lengths(input);
 
         let mul = Self::mult_complexity(core::cmp::max(mod_len, base_len))?;
-        let adj = core::cmp::max(Self::adj_exp_len(exp_len, base_len, &input), U256::from(1))
-            / U256::from(20);
+        let adj = core::cmp::max(Self::adj_exp_len(exp_len, base_len, &input), U256::from(1));
         let (gas_val, overflow) = mul.overflowing_mul(adj);
-        if overflow {
+        let gas_val = gas_val / U256::from(20);
+        if overflow || gas_val > U256::from(u64::MAX) {
             Err(ExitError::OutOfGas)
         } else {
             Ok(gas_val.as_u64())
@@ -70,80 +144,66 @@ impl Precompile for ModExp<Byzantium> {
     /// See: https://eips.ethereum.org/EIPS/eip-198
     /// See: https://etherscan.io/address/0000000000000000000000000000000000000005
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             return Err(ExitError::OutOfGas);
         }
 
-        let base_len = U256::from(&input[0..32]);
-        let exp_len = U256::from(&input[32..64]);
-        let mod_len = U256::from(&input[64..96]);
-
-        let base_len = base_len.as_usize();
-        let mut base_bytes = Vec::with_capacity(32);
-        for i in 0..base_len {
-            if 96 + i >= input.len() {
-                base_bytes.push(0u8);
-            } else {
-                base_bytes.push(input[96 + i]);
-            }
-        }
+        Self::run_inner(input, cost)
+    }
+}
 
-        let exp_len = exp_len.as_usize();
-        let mut exp_bytes = Vec::with_capacity(32);
-        for i in 0..exp_len {
-            if 96 + base_len + i >= input.len() {
-                exp_bytes.push(0u8);
-            } else {
-                exp_bytes.push(input[96 + base_len + i]);
-            }
+impl ModExp<Berlin> {
+    /// The EIP-2565 multiplication complexity: the square of the number of
+    /// 8-byte words in the longer of the base and the modulus.
+    fn mult_complexity(x: U256) -> Result<U256, ExitError> {
+        // Rounds up without `x + 7`, which could overflow.
+        let mut words = x / U256::from(8);
+        if !(x % U256::from(8)).is_zero() {
+            words += U256::one();
         }
-
-        let mod_len = mod_len.as_usize();
-        let mut mod_bytes = Vec::with_capacity(32);
-        for i in 0..mod_len {
-            if 96 + base_len + exp_len + i >= input.len() {
-                mod_bytes.push(0u8);
-            } else {
-                mod_bytes.push(input[96 + base_len + exp_len + i]);
-            }
+        let (complexity, overflow) = words.overflowing_mul(words);
+        if overflow {
+            Err(ExitError::OutOfGas)
+        } else {
+            Ok(complexity)
         }
-
-        let base = BigUint::from_bytes_be(&base_bytes);
-        let exponent = BigUint::from_bytes_be(&exp_bytes);
-        let modulus = BigUint::from_bytes_be(&mod_bytes);
-
-        let result = {
-            let computed_result = base.modpow(&exponent, &modulus).to_bytes_be();
-            // The result must be the same length as the input modulus.
-            // To ensure this we pad on the left with zeros.
-            if mod_len > computed_result.len() {
-                let diff = mod_len - computed_result.len();
-                let mut padded_result = Vec::with_capacity(mod_len);
-                padded_result.extend(core::iter::repeat(0).take(diff));
-                padded_result.extend_from_slice(&computed_result);
-                padded_result
-            } else {
-                computed_result
-            }
-        };
-
-        Ok((ExitSucceed::Returned, result, 0))
     }
 }
 
 impl Precompile for ModExp<Berlin> {
-    fn required_gas(_input: &[u8]) -> Result<u64, ExitError> {
-        todo!()
+    fn required_gas(input: &[u8]) -> Result<u64, ExitError> {
+        let (base_len, exp_len, mod_len) = parse_lengths(input);
+
+        let mul = Self::mult_complexity(core::cmp::max(mod_len, base_len))?;
+        let adj = core::cmp::max(Self::adj_exp_len(exp_len, base_len, &input), U256::from(1));
+        let (gas_val, overflow) = mul.overflowing_mul(adj);
+        let gas_val = core::cmp::max(gas_val / U256::from(3), U256::from(200));
+        if overflow || gas_val > U256::from(u64::MAX) {
+            Err(ExitError::OutOfGas)
+        } else {
+            Ok(gas_val.as_u64())
+        }
     }
 
-    fn run(_input: &[u8], _target_gas: u64, _context: &Context) -> PrecompileResult {
-        todo!()
+    /// Same as the Byzantium modexp, with the cheaper pricing of EIP-2565.
+    ///
+    /// See: https://eips.ethereum.org/EIPS/eip-2565
+    fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
+            return Err(ExitError::OutOfGas);
+        }
+
+        Self::run_inner(input, cost)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
+    use proptest::prelude::*;
 
     fn new_context() -> Context {
         Context {
@@ -165,9 +225,10 @@ mod tests {
             fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
         )
         .unwrap();
-        let modexp_res = ModExp::<Byzantium>::run(&test_input1, 12_288, &new_context())
+        // 13_056 is the cost given in the EIP-198 example for this input.
+        let modexp_res = ModExp::<Byzantium>::run(&test_input1, 13_056, &new_context())
             .unwrap()
-            .1;
+            .output;
         let res = U256::from_big_endian(&modexp_res);
 
         assert_eq!(res, U256::from(1));
@@ -180,9 +241,9 @@ mod tests {
             fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
         )
         .unwrap();
-        let modexp_res = ModExp::<Byzantium>::run(&test_input2, 12_288, &new_context())
+        let modexp_res = ModExp::<Byzantium>::run(&test_input2, 13_056, &new_context())
             .unwrap()
-            .1;
+            .output;
         let res = U256::from_big_endian(&modexp_res);
 
         assert_eq!(res, U256::from(0));
@@ -213,7 +274,7 @@ mod tests {
         );
         let modexp_res = ModExp::<Byzantium>::run(&test_input4, 12_288, &new_context())
             .unwrap()
-            .1;
+            .output;
         let res = U256::from_big_endian(&modexp_res);
         assert_eq!(res, expected);
 
@@ -232,8 +293,202 @@ mod tests {
         );
         let modexp_res = ModExp::<Byzantium>::run(&test_input5, 12_288, &new_context())
             .unwrap()
-            .1;
+            .output;
         let res = U256::from_big_endian(&modexp_res);
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn test_modexp_berlin() {
+        // The first EIP-198 example: a 32-byte modulus and a 255-bit exponent.
+        let input = hex::decode(
+            "\
+            0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            03\
+            fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+            fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        )
+        .unwrap();
+        assert_eq!(ModExp::<Byzantium>::required_gas(&input).unwrap(), 13_056);
+        assert_eq!(ModExp::<Berlin>::required_gas(&input).unwrap(), 1_360);
+
+        let res = ModExp::<Berlin>::run(&input, 1_360, &new_context()).unwrap();
+        assert_eq!(res.cost, 1_360);
+        assert_eq!(U256::from_big_endian(&res.output), U256::from(1));
+        let res = ModExp::<Berlin>::run(&input, 1_359, &new_context());
+        assert!(matches!(res, Err(ExitError::OutOfGas)));
+
+        // Small inputs cost the EIP-2565 minimum.
+        let input = hex::decode(
+            "0000000000000000000000000000000000000000000000000000000000000001\
+            0000000000000000000000000000000000000000000000000000000000000002\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            03\
+            ffff\
+            8000000000000000000000000000000000000000000000000000000000000000\
+            07",
+        )
+        .unwrap();
+        assert_eq!(ModExp::<Berlin>::required_gas(&input).unwrap(), 200);
+        assert_eq!(
+            ModExp::<Berlin>::run(&input, 200, &new_context())
+                .unwrap()
+                .output,
+            ModExp::<Byzantium>::run(&input, u64::MAX, &new_context())
+                .unwrap()
+                .output,
+        );
+
+        // Lengths whose word count squared overflows.
+        let input = encode_input(0, 0, 0, &[]);
+        let mut huge = input.clone();
+        for byte in &mut huge[64..96] {
+            *byte = 0xff;
+        }
+        assert!(matches!(
+            ModExp::<Berlin>::required_gas(&huge),
+            Err(ExitError::OutOfGas)
+        ));
+        assert_eq!(ModExp::<Berlin>::required_gas(&input).unwrap(), 200);
+    }
+
+    #[test]
+    fn test_modexp_vectors() {
+        test_vectors::check_vectors::<ModExp<Byzantium>>("modexp.json");
+        test_vectors::check_vectors::<ModExp<Berlin>>("modexp_eip2565.json");
+    }
+
+    /// Square-and-multiply on operands small enough to compute in `u128`.
+    fn reference_modpow(base: u64, exponent: u64, modulus: u64) -> u64 {
+        let modulus = modulus as u128;
+        let mut result = 1 % modulus;
+        let mut base = base as u128 % modulus;
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base % modulus;
+            }
+            base = base * base % modulus;
+            exponent >>= 1;
+        }
+        result as u64
+    }
+
+    /// Number of significant bits of a big-endian integer.
+    fn bit_len(bytes: &[u8]) -> u128 {
+        match bytes.iter().position(|b| *b != 0) {
+            None => 0,
+            Some(i) => ((bytes.len() - i) * 8) as u128 - bytes[i].leading_zeros() as u128,
+        }
+    }
+
+    /// The EIP-198 adjusted exponent length, for lengths small enough to compute in `u128`.
+    fn reference_adj_exp_len(base_len: usize, exp_len: usize, mod_len: usize, body: &[u8]) -> u128 {
+        let mut padded = body.to_vec();
+        padded.resize(base_len + exp_len + mod_len, 0);
+        let exp_head = &padded[base_len..base_len + core::cmp::min(exp_len, 32)];
+        let head_bits = bit_len(exp_head).saturating_sub(1);
+        if exp_len <= 32 {
+            head_bits
+        } else {
+            8 * (exp_len as u128 - 32) + head_bits
+        }
+    }
+
+    /// The EIP-198 gas formula, for lengths small enough to compute in `u128`.
+    fn reference_gas(base_len: usize, exp_len: usize, mod_len: usize, body: &[u8]) -> u64 {
+        let x = core::cmp::max(base_len, mod_len) as u128;
+        let mult_complexity = if x <= 64 {
+            x * x
+        } else if x <= 1_024 {
+            x * x / 4 + 96 * x - 3_072
+        } else {
+            x * x / 16 + 480 * x - 199_680
+        };
+
+        let adj_exp_len = reference_adj_exp_len(base_len, exp_len, mod_len, body);
+        (mult_complexity * core::cmp::max(adj_exp_len, 1) / 20) as u64
+    }
+
+    /// The EIP-2565 gas formula, for lengths small enough to compute in `u128`.
+    fn reference_gas_berlin(base_len: usize, exp_len: usize, mod_len: usize, body: &[u8]) -> u64 {
+        let words = (core::cmp::max(base_len, mod_len) as u128 + 7) / 8;
+        let adj_exp_len = reference_adj_exp_len(base_len, exp_len, mod_len, body);
+        core::cmp::max(words * words * core::cmp::max(adj_exp_len, 1) / 3, 200) as u64
+    }
+
+    fn encode_input(base_len: usize, exp_len: usize, mod_len: usize, body: &[u8]) -> Vec<u8> {
+        let mut input = Vec::with_capacity(96 + body.len());
+        for len in &[base_len, exp_len, mod_len] {
+            let mut word = [0u8; 32];
+            U256::from(*len).to_big_endian(&mut word);
+            input.extend_from_slice(&word);
+        }
+        input.extend_from_slice(body);
+        input
+    }
+
+    proptest! {
+        #[test]
+        fn test_modexp_matches_reference(
+            base in any::<u64>(),
+            exponent in any::<u64>(),
+            modulus in 1..u64::MAX,
+        ) {
+            let mut body = Vec::with_capacity(24);
+            body.extend_from_slice(&base.to_be_bytes());
+            body.extend_from_slice(&exponent.to_be_bytes());
+            body.extend_from_slice(&modulus.to_be_bytes());
+            let input = encode_input(8, 8, 8, &body);
+
+            let gas = ModExp::<Byzantium>::required_gas(&input).unwrap();
+            let res = ModExp::<Byzantium>::run(&input, gas, &new_context()).unwrap().output;
+            let expected = reference_modpow(base, exponent, modulus);
+            prop_assert_eq!(res, expected.to_be_bytes().to_vec());
+        }
+
+        #[test]
+        fn test_modexp_gas_matches_reference(
+            base_len in 0usize..80,
+            exp_len in 0usize..80,
+            mod_len in 0usize..80,
+            body in prop::collection::vec(any::<u8>(), 0..240),
+        ) {
+            let input = encode_input(base_len, exp_len, mod_len, &body);
+            let gas = ModExp::<Byzantium>::required_gas(&input).unwrap();
+            prop_assert_eq!(gas, reference_gas(base_len, exp_len, mod_len, &body));
+
+            // Short bodies are zero-padded, so the output always has `mod_len` bytes.
+            let res = ModExp::<Byzantium>::run(&input, gas, &new_context()).unwrap().output;
+            prop_assert_eq!(res.len(), mod_len);
+        }
+
+        #[test]
+        fn test_modexp_berlin_gas_matches_reference(
+            base_len in 0usize..80,
+            exp_len in 0usize..80,
+            mod_len in 0usize..80,
+            body in prop::collection::vec(any::<u8>(), 0..240),
+        ) {
+            let input = encode_input(base_len, exp_len, mod_len, &body);
+            let gas = ModExp::<Berlin>::required_gas(&input).unwrap();
+            prop_assert_eq!(gas, reference_gas_berlin(base_len, exp_len, mod_len, &body));
+
+            // Only the pricing changed.
+            let res = ModExp::<Berlin>::run(&input, gas, &new_context()).unwrap().output;
+            let expected = ModExp::<Byzantium>::run(&input, u64::MAX, &new_context())
+                .unwrap()
+                .output;
+            prop_assert_eq!(res, expected);
+        }
+
+        #[test]
+        fn test_modexp_arbitrary_input(input in prop::collection::vec(any::<u8>(), 0..256)) {
+            // Must not panic, whatever the outcome.
+            let _ = ModExp::<Byzantium>::run(&input, 100_000, &new_context());
+            let _ = ModExp::<Berlin>::run(&input, 100_000, &new_context());
+        }
+    }
 }
@@ -0,0 +1,44 @@
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
+use crate::prelude::*;
+use crate::sdk;
+use evm::{Context, ExitError};
+
+/// NEAR block info costs.
+mod costs {
+    /// The cost of reading the block info, three host calls.
+    pub(super) const NEAR_BLOCK_INFO: u64 = 40;
+}
+
+pub(super) struct NearBlockInfo;
+
+impl NearBlockInfo {
+    /// `keccak256("nearBlockInfo")[12..]`
+    pub(super) const ADDRESS: Address = H160([
+        0x3d, 0xd1, 0x1d, 0x74, 0x4b, 0xda, 0xdd, 0x17, 0x98, 0xde, 0x86, 0x19, 0x2d, 0xc7, 0x9a,
+        0xf7, 0xb1, 0x02, 0x63, 0x85,
+    ]);
+}
+
+impl Precompile for NearBlockInfo {
+    fn required_gas(_input: &[u8]) -> Result<u64, ExitError> {
+        Ok(costs::NEAR_BLOCK_INFO)
+    }
+
+    /// Returns the NEAR block the transaction executes in, ABI-encoded as
+    /// `(uint64 height, uint64 timestampNanoseconds, uint64 epochHeight)`.
+    /// The input is ignored.
+    ///
+    /// NEAR contracts can only see the epoch height, not the epoch id.
+    fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
+            return Err(ExitError::OutOfGas);
+        }
+
+        let mut output = vec![0u8; 96];
+        output[24..32].copy_from_slice(&sdk::block_index().to_be_bytes());
+        output[56..64].copy_from_slice(&sdk::block_timestamp().to_be_bytes());
+        output[88..96].copy_from_slice(&sdk::epoch_height().to_be_bytes());
+        Ok(PrecompileOutput::without_logs(cost, output))
+    }
+}
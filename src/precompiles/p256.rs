@@ -0,0 +1,106 @@
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
+use crate::prelude::*;
+use ecdsa::hazmat::VerifyPrimitive;
+use evm::{Context, ExitError};
+use p256::ecdsa::Signature;
+use p256::elliptic_curve::sec1::FromEncodedPoint;
+use p256::{AffinePoint, EncodedPoint, FieldBytes, Scalar};
+
+/// P-256 verification costs.
+mod costs {
+    /// The cost of a signature verification, set by RIP-7212.
+    pub(super) const P256_VERIFY_BASE: u64 = 3_450;
+}
+
+mod consts {
+    pub(super) const INPUT_LEN: usize = 160;
+}
+
+/// The secp256r1 signature verification precompile of RIP-7212, used by
+/// passkey and WebAuthn wallets.
+pub(super) struct P256Verify;
+
+impl P256Verify {
+    /// The address RIP-7212 assigns, the first one past the single-byte range
+    /// of the Ethereum precompiles.
+    pub(super) const ADDRESS: Address = H160([
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0x00,
+    ]);
+}
+
+/// Verifies an ECDSA signature over the P-256 curve of an already hashed
+/// message, returning `false` for a malformed signature or public key.
+fn verify(input: &[u8]) -> bool {
+    let (hash, rest) = input.split_at(32);
+    let (r, rest) = rest.split_at(32);
+    let (s, rest) = rest.split_at(32);
+    let (x, y) = rest.split_at(32);
+
+    // Rejects `r` or `s` of zero or not below the group order.
+    let signature = match Signature::from_scalars(
+        FieldBytes::clone_from_slice(r),
+        FieldBytes::clone_from_slice(s),
+    ) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    // Rejects coordinates not below the field modulus, and points off the
+    // curve, which includes the all-zero encoding of the point at infinity.
+    let point = EncodedPoint::from_affine_coordinates(
+        &FieldBytes::clone_from_slice(x),
+        &FieldBytes::clone_from_slice(y),
+        false,
+    );
+    let public_key = match AffinePoint::from_encoded_point(&point) {
+        Some(public_key) => public_key,
+        None => return false,
+    };
+    let hash = Scalar::from_bytes_reduced(&FieldBytes::clone_from_slice(hash));
+
+    public_key.verify_prehashed(&hash, &signature).is_ok()
+}
+
+impl Precompile for P256Verify {
+    fn required_gas(_input: &[u8]) -> Result<u64, ExitError> {
+        Ok(costs::P256_VERIFY_BASE)
+    }
+
+    /// Verifies a P-256 signature.
+    ///
+    /// The input is the 32-byte message hash, the signature's `r` and `s`,
+    /// and the public key's `x` and `y`, each 32 bytes. Returns a 32-byte word
+    /// of 1 if the signature is valid, and nothing if it is not or the input
+    /// is not exactly 160 bytes, as RIP-7212 specifies.
+    fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
+            return Err(ExitError::OutOfGas);
+        }
+
+        let output = if input.len() == consts::INPUT_LEN && verify(input) {
+            let mut output = vec![0u8; 32];
+            output[31] = 1;
+            output
+        } else {
+            Vec::new()
+        };
+        Ok(PrecompileOutput::without_logs(cost, output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::precompiles::test_vectors;
+
+    #[test]
+    fn test_address() {
+        assert_eq!(P256Verify::ADDRESS, Address::from_low_u64_be(0x100));
+    }
+
+    #[test]
+    fn test_p256_verify() {
+        test_vectors::check_vectors::<P256Verify>("p256Verify.json");
+    }
+}
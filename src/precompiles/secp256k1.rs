@@ -1,7 +1,7 @@
-use crate::precompiles::{Precompile, PrecompileResult};
+use crate::precompiles::{Precompile, PrecompileOutput, PrecompileResult};
 use crate::prelude::*;
 use ethabi::Address;
-use evm::{Context, ExitError, ExitSucceed};
+use evm::{Context, ExitError};
 
 mod costs {
     pub(super) const ECRECOVER_BASE: u64 = 3_000;
@@ -17,7 +17,6 @@ mod consts {
 // Quite a few library methods rely on this and that should be changed. This
 // should only be for precompiles.
 pub(crate) fn ecrecover(hash: H256, signature: &[u8]) -> Result<Address, ExitError> {
-    use sha3::Digest;
     assert_eq!(signature.len(), 65);
 
     let hash = secp256k1::Message::parse_slice(hash.as_bytes()).unwrap();
@@ -31,7 +30,7 @@ pub(crate) fn ecrecover(hash: H256, signature: &[u8]) -> Result<Address, ExitErr
     if let Ok(recovery_id) = secp256k1::RecoveryId::parse(bit) {
         if let Ok(public_key) = secp256k1::recover(&hash, &signature, &recovery_id) {
             // recover returns a 65-byte key, but addresses come from the raw 64-byte key
-            let r = sha3::Keccak256::digest(&public_key.serialize()[1..]);
+            let r = crate::types::keccak(&public_key.serialize()[1..]);
             return Ok(Address::from_slice(&r[12..]));
         }
     }
@@ -47,7 +46,8 @@ impl Precompile for ECRecover {
     }
 
     fn run(input: &[u8], target_gas: u64, _context: &Context) -> PrecompileResult {
-        if Self::required_gas(input)? > target_gas {
+        let cost = Self::required_gas(input)?;
+        if cost > target_gas {
             return Err(ExitError::OutOfGas);
         }
 
@@ -67,7 +67,8 @@ impl Precompile for ECRecover {
         let v_bit = match v[31] {
             27 | 28 if v[..31] == [0; 31] => v[31] - 27,
             _ => {
-                return Ok((ExitSucceed::Returned, vec![255u8; 32], 0)); // Not confident on this return.
+                // Not confident on this return.
+                return Ok(PrecompileOutput::without_logs(cost, vec![255u8; 32]));
             }
         };
         signature[64] = v_bit; // v
@@ -84,13 +85,15 @@ impl Precompile for ECRecover {
             }
         };
 
-        Ok((ExitSucceed::Returned, output.to_vec(), 0))
+        Ok(PrecompileOutput::without_logs(cost, output.to_vec()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::precompiles::test_vectors;
+    use proptest::prelude::*;
 
     fn new_context() -> Context {
         Context {
@@ -125,7 +128,9 @@ mod tests {
             hex::decode("000000000000000000000000c08b5542d177ac6686946920409741463a15dddb")
                 .unwrap();
 
-        let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().1;
+        let res = ECRecover::run(&input, 3_000, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         // out of gas
@@ -140,7 +145,9 @@ mod tests {
             hex::decode("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
                 .unwrap();
 
-        let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().1;
+        let res = ECRecover::run(&input, 3_000, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         let input = hex::decode("47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000000000000000000000000000000000000000000000000000001b000000000000000000000000000000000000000000000000000000000000001b0000000000000000000000000000000000000000000000000000000000000000").unwrap();
@@ -148,7 +155,9 @@ mod tests {
             hex::decode("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
                 .unwrap();
 
-        let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().1;
+        let res = ECRecover::run(&input, 3_000, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         let input = hex::decode("47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000000000000000000000000000000000000000000000000000001b0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001b").unwrap();
@@ -156,7 +165,9 @@ mod tests {
             hex::decode("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
                 .unwrap();
 
-        let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().1;
+        let res = ECRecover::run(&input, 3_000, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         let input = hex::decode("47173285a8d7341e5e972fc677286384f802f8ef42a5ec5f03bbfa254cb01fad000000000000000000000000000000000000000000000000000000000000001bffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff000000000000000000000000000000000000000000000000000000000000001b").unwrap();
@@ -164,7 +175,9 @@ mod tests {
             hex::decode("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff")
                 .unwrap();
 
-        let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().1;
+        let res = ECRecover::run(&input, 3_000, &new_context())
+            .unwrap()
+            .output;
         assert_eq!(res, expected);
 
         // Why is this test returning an address???
@@ -174,4 +187,45 @@ mod tests {
         // let res = ecrecover_raw(&input, Some(500)).unwrap().1;
         // assert_eq!(res, expected);
     }
+
+    #[test]
+    fn test_ecrecover_vectors() {
+        test_vectors::check_vectors::<ECRecover>("ecRecover.json");
+    }
+
+    proptest! {
+        #[test]
+        fn test_ecrecover_matches_signer(seed in "[a-z0-9]{1,32}", hash in any::<[u8; 32]>()) {
+            use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
+
+            // near-crypto signs with the reference libsecp256k1 implementation.
+            let signer = InMemorySigner::from_seed("test", KeyType::SECP256K1, &seed);
+            let signer_address = match signer.public_key.clone() {
+                PublicKey::SECP256K1(public_key) => {
+                    let public_key: [u8; 64] = public_key.into();
+                    Address::from_slice(&crate::types::keccak(&public_key)[12..])
+                }
+                PublicKey::ED25519(_) => unreachable!(),
+            };
+            let signature: [u8; 65] = match signer.sign(&hash) {
+                Signature::SECP256K1(signature) => signature.into(),
+                Signature::ED25519(_) => unreachable!(),
+            };
+
+            let mut input = [0u8; 128];
+            input[0..32].copy_from_slice(&hash);
+            input[63] = signature[64] + 27;
+            input[64..128].copy_from_slice(&signature[0..64]);
+
+            let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().output;
+            prop_assert_eq!(&res[0..12], &[0u8; 12][..]);
+            prop_assert_eq!(&res[12..], signer_address.as_bytes());
+        }
+
+        #[test]
+        fn test_ecrecover_arbitrary_input(input in prop::collection::vec(any::<u8>(), 0..192)) {
+            let res = ECRecover::run(&input, 3_000, &new_context()).unwrap().output;
+            prop_assert_eq!(res.len(), 32);
+        }
+    }
 }
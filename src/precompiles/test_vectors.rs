@@ -0,0 +1,241 @@
+//! Precompile test vectors, read from `src/precompiles/testdata`.
+//!
+//! The files use the go-ethereum `core/vm/testdata/precompiles` format, so
+//! vectors from there and from the EIPs can be dropped in as they are.
+//! Successful cases go in `<name>.json`, failing cases in `fail-<name>.json`.
+//!
+//! The vectors come from:
+//!
+//! * go-ethereum's `core/vm/testdata/precompiles`: the `bn256*`, `blake2F`
+//!   and `ecRecover` vectors, and the failing cases of the same names. The
+//!   `blake2F` ones are those of EIP-152;
+//! * the two examples of EIP-198 in `modexp.json`, priced by EIP-2565 in
+//!   `modexp_eip2565.json`;
+//! * the published test strings of SHA-256 (FIPS 180-2) and RIPEMD-160;
+//! * `identity.json`, written by hand, its output being its input;
+//! * `testdata/generate.py`, for the `gen-*` P256Verify vectors, as RIP-7212
+//!   has none in this format. It computes them with a reference
+//!   implementation that shares no code with the precompile.
+//!
+//! The `conformance` directory holds cases that go through the hard fork
+//! dispatch functions instead, so regression cases can be added without any
+//! Rust changes.
+
+use crate::precompiles::{
+    berlin_precompiles, byzantium_precompiles, homestead_precompiles, istanbul_precompiles,
+    EvmPrecompileResult, Precompile,
+};
+use crate::prelude::*;
+use evm::{Context, ExitError};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Vector {
+    input: String,
+    expected: String,
+    name: String,
+    gas: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FailVector {
+    input: String,
+    expected_error: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConformanceCase {
+    name: String,
+    hardfork: String,
+    address: String,
+    input: String,
+    #[serde(default)]
+    expected: Option<String>,
+    #[serde(default)]
+    expected_error: Option<String>,
+    gas: u64,
+}
+
+type Precompiles = fn(Address, &[u8], Option<u64>, &Context) -> Option<EvmPrecompileResult>;
+
+fn new_context() -> Context {
+    Context {
+        address: Default::default(),
+        caller: Default::default(),
+        apparent_value: Default::default(),
+    }
+}
+
+fn testdata_path(file: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/precompiles/testdata")
+        .join(file)
+}
+
+fn read<T: DeserializeOwned>(file: &str) -> Vec<T> {
+    let path = testdata_path(file).display().to_string();
+    let json = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    let vectors: Vec<T> = serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    assert!(!vectors.is_empty(), "{}: no test vectors", path);
+    vectors
+}
+
+/// Checks the output and gas cost of `P` against every vector in `file`,
+/// that it reports that cost as used, and that it runs out of gas one unit
+/// below the cost.
+pub(super) fn check_vectors<P: Precompile>(file: &str) {
+    let context = new_context();
+    for vector in read::<Vector>(file) {
+        let input = hex::decode(&vector.input).unwrap();
+        let expected = hex::decode(&vector.expected).unwrap();
+
+        let gas = P::required_gas(&input);
+        assert_eq!(gas.ok(), Some(vector.gas), "{}: {}", file, vector.name);
+
+        let res = P::run(&input, vector.gas, &context)
+            .unwrap_or_else(|e| panic!("{}: {}: {:?}", file, vector.name, e));
+        assert_eq!(res.output, expected, "{}: {}", file, vector.name);
+        assert_eq!(res.cost, vector.gas, "{}: {}", file, vector.name);
+
+        if vector.gas > 0 {
+            let res = P::run(&input, vector.gas - 1, &context);
+            assert!(
+                matches!(res, Err(ExitError::OutOfGas)),
+                "{}: {}",
+                file,
+                vector.name
+            );
+        }
+    }
+}
+
+/// Checks the output of `P` against every vector in `file`, for hard forks
+/// whose gas schedule differs from the one the vectors were written for.
+/// `P` still has to succeed with exactly its own cost and run out of gas one
+/// unit below it.
+pub(super) fn check_outputs<P: Precompile>(file: &str) {
+    let context = new_context();
+    for vector in read::<Vector>(file) {
+        let input = hex::decode(&vector.input).unwrap();
+        let expected = hex::decode(&vector.expected).unwrap();
+
+        let gas = P::required_gas(&input)
+            .unwrap_or_else(|e| panic!("{}: {}: {:?}", file, vector.name, e));
+        let res = P::run(&input, gas, &context)
+            .unwrap_or_else(|e| panic!("{}: {}: {:?}", file, vector.name, e));
+        assert_eq!(res.output, expected, "{}: {}", file, vector.name);
+        assert_eq!(res.cost, gas, "{}: {}", file, vector.name);
+
+        if gas > 0 {
+            let res = P::run(&input, gas - 1, &context);
+            assert!(
+                matches!(res, Err(ExitError::OutOfGas)),
+                "{}: {}",
+                file,
+                vector.name
+            );
+        }
+    }
+}
+
+/// Checks that `P` fails with the expected error on every vector in `file`.
+pub(super) fn check_failures<P: Precompile>(file: &str) {
+    let context = new_context();
+    for vector in read::<FailVector>(file) {
+        let input = hex::decode(&vector.input).unwrap();
+
+        match P::run(&input, u64::MAX, &context) {
+            Err(ExitError::Other(e)) => assert_eq!(
+                &*e,
+                vector.expected_error.as_str(),
+                "{}: {}",
+                file,
+                vector.name
+            ),
+            res => panic!(
+                "{}: {}: expected {:?}, got {:?}",
+                file, vector.name, vector.expected_error, res
+            ),
+        }
+    }
+}
+
+fn hardfork_precompiles(hardfork: &str) -> Precompiles {
+    match hardfork {
+        "homestead" => homestead_precompiles,
+        "byzantium" => byzantium_precompiles,
+        "istanbul" => istanbul_precompiles,
+        "berlin" => berlin_precompiles,
+        _ => panic!("unknown hard fork {:?}", hardfork),
+    }
+}
+
+/// Runs every case in every `*.json` file of `dir` through the dispatch
+/// function of the case's hard fork.
+///
+/// A case either expects an output, in which case it must succeed using
+/// exactly `Gas` and run out of gas below it, or an error.
+pub(super) fn check_conformance(dir: &str) {
+    let mut files: Vec<_> = std::fs::read_dir(testdata_path(dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "{}: no conformance cases", dir);
+
+    let context = new_context();
+    for path in files {
+        let file = format!("{}/{}", dir, path.file_name().unwrap().to_string_lossy());
+        for case in read::<ConformanceCase>(&file) {
+            let precompiles = hardfork_precompiles(&case.hardfork);
+            let address = Address::from_slice(&hex::decode(&case.address).unwrap());
+            let input = hex::decode(&case.input).unwrap();
+            let run = |gas| {
+                precompiles(address, &input, Some(gas), &context)
+                    .unwrap_or_else(|| panic!("{}: {}: not a precompile", file, case.name))
+            };
+
+            match (&case.expected, &case.expected_error) {
+                (Some(expected), None) => {
+                    let res = run(case.gas)
+                        .unwrap_or_else(|e| panic!("{}: {}: {:?}", file, case.name, e));
+                    assert_eq!(
+                        res.1,
+                        hex::decode(expected).unwrap(),
+                        "{}: {}",
+                        file,
+                        case.name
+                    );
+                    assert_eq!(res.2, case.gas, "{}: {}", file, case.name);
+                    if case.gas > 0 {
+                        assert!(
+                            matches!(run(case.gas - 1), Err(ExitError::OutOfGas)),
+                            "{}: {}",
+                            file,
+                            case.name
+                        );
+                    }
+                }
+                (None, Some(expected_error)) => match run(case.gas) {
+                    Err(ExitError::Other(e)) => {
+                        assert_eq!(&*e, expected_error.as_str(), "{}: {}", file, case.name)
+                    }
+                    res => panic!(
+                        "{}: {}: expected {:?}, got {:?}",
+                        file, case.name, expected_error, res
+                    ),
+                },
+                _ => panic!(
+                    "{}: {}: exactly one of Expected and ExpectedError must be set",
+                    file, case.name
+                ),
+            }
+        }
+    }
+}
@@ -13,8 +13,8 @@ pub use alloc::{
 pub use core::{convert::TryInto, marker::PhantomData, mem};
 #[cfg(feature = "std")]
 pub use std::{
-    borrow::Cow::Borrowed, borrow::ToOwned, boxed::Box, collections::HashMap, convert::TryInto,
-    marker::PhantomData, mem, string::String, string::ToString, vec, vec::Vec,
+    borrow::Cow, borrow::Cow::Borrowed, borrow::ToOwned, boxed::Box, collections::HashMap,
+    convert::TryInto, marker::PhantomData, mem, string::String, string::ToString, vec, vec::Vec,
 };
 
 pub use primitive_types::{H160, H256, U256};
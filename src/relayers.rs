@@ -0,0 +1,97 @@
+//! Relayer allowlist.
+//!
+//! When the allowlist is on, only the NEAR accounts the owner allowed can
+//! submit signed transactions with `raw_call`, each at most its quota of
+//! transactions per NEAR block. Other methods are not affected.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::prelude::Vec;
+use crate::sdk;
+use crate::types::LimitError;
+
+/// Key of the flag turning the allowlist on, present while it is on.
+const ALLOWLIST_KEY: &[u8; 18] = b"\0RELAYER_ALLOWLIST";
+const RELAYER_PREFIX: &[u8; 9] = b"\0RELAYER:";
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Relayer {
+    transactions_per_block: u64,
+    /// Block the relayer last submitted a transaction in.
+    block: u64,
+    /// Transactions the relayer submitted in `block`.
+    transactions: u64,
+}
+
+fn relayer_key(account_id: &[u8]) -> Vec<u8> {
+    [&RELAYER_PREFIX[..], account_id].concat()
+}
+
+fn get_relayer(account_id: &[u8]) -> Option<Relayer> {
+    sdk::read_storage(&relayer_key(account_id))
+        .map(|bytes| Relayer::try_from_slice(&bytes).expect("ERR_DESER"))
+}
+
+fn set_relayer(account_id: &[u8], relayer: &Relayer) {
+    sdk::write_storage(
+        &relayer_key(account_id),
+        &relayer.try_to_vec().expect("ERR_SER"),
+    );
+}
+
+pub fn is_allowlist_enabled() -> bool {
+    sdk::storage_has_key(ALLOWLIST_KEY)
+}
+
+pub fn set_allowlist_enabled(enabled: bool) {
+    if enabled {
+        sdk::write_storage(ALLOWLIST_KEY, &[]);
+    } else {
+        sdk::remove_storage(ALLOWLIST_KEY);
+    }
+}
+
+/// Allows a relayer, or changes its quota if already allowed.
+pub fn allow(account_id: &[u8], transactions_per_block: u64) {
+    let relayer = match get_relayer(account_id) {
+        Some(relayer) => Relayer {
+            transactions_per_block,
+            ..relayer
+        },
+        None => Relayer {
+            transactions_per_block,
+            block: 0,
+            transactions: 0,
+        },
+    };
+    set_relayer(account_id, &relayer);
+}
+
+pub fn disallow(account_id: &[u8]) {
+    sdk::remove_storage(&relayer_key(account_id));
+}
+
+/// Returns the quota of an allowed relayer.
+pub fn get_quota(account_id: &[u8]) -> Option<u64> {
+    get_relayer(account_id).map(|relayer| relayer.transactions_per_block)
+}
+
+/// Counts a transaction submitted by a relayer against its quota, if the
+/// allowlist is on.
+pub fn use_quota(account_id: &[u8]) -> Result<(), LimitError> {
+    if !is_allowlist_enabled() {
+        return Ok(());
+    }
+    let mut relayer = get_relayer(account_id).ok_or(LimitError::RelayerNotAllowed)?;
+    let block = sdk::block_index();
+    if relayer.block != block {
+        relayer.block = block;
+        relayer.transactions = 0;
+    }
+    if relayer.transactions >= relayer.transactions_per_block {
+        return Err(LimitError::RelayerQuotaExceeded);
+    }
+    relayer.transactions += 1;
+    set_relayer(account_id, &relayer);
+    Ok(())
+}
@@ -1,6 +1,7 @@
-use crate::prelude::{vec, String, Vec, H256};
+use crate::prelude::{vec, Cow, String, Vec, H256};
 use crate::types::STORAGE_PRICE_PER_BYTE;
 use borsh::{BorshDeserialize, BorshSerialize};
+use core::cell::UnsafeCell;
 
 mod exports {
 
@@ -22,7 +23,7 @@ mod exports {
         // TODO #1903 fn block_height() -> u64;
         pub(crate) fn block_index() -> u64;
         pub(crate) fn block_timestamp() -> u64;
-        fn epoch_height() -> u64;
+        pub(crate) fn epoch_height() -> u64;
         pub(crate) fn storage_usage() -> u64;
         // #################
         // # Economics API #
@@ -30,7 +31,7 @@ mod exports {
         fn account_balance(balance_ptr: u64);
         pub(crate) fn attached_deposit(balance_ptr: u64);
         pub(crate) fn prepaid_gas() -> u64;
-        fn used_gas() -> u64;
+        pub(crate) fn used_gas() -> u64;
         // ############
         // # Math API #
         // ############
@@ -82,7 +83,7 @@ mod exports {
             code_len: u64,
             code_ptr: u64,
         );
-        fn promise_batch_action_function_call(
+        pub(crate) fn promise_batch_action_function_call(
             promise_index: u64,
             method_name_len: u64,
             method_name_ptr: u64,
@@ -145,9 +146,17 @@ mod exports {
         pub(crate) fn storage_remove(key_len: u64, key_ptr: u64, register_id: u64) -> u64;
         pub(crate) fn storage_has_key(key_len: u64, key_ptr: u64) -> u64;
         fn storage_iter_prefix(prefix_len: u64, prefix_ptr: u64) -> u64;
-        fn storage_iter_range(start_len: u64, start_ptr: u64, end_len: u64, end_ptr: u64) -> u64;
-        fn storage_iter_next(iterator_id: u64, key_register_id: u64, value_register_id: u64)
-            -> u64;
+        pub(crate) fn storage_iter_range(
+            start_len: u64,
+            start_ptr: u64,
+            end_len: u64,
+            end_ptr: u64,
+        ) -> u64;
+        pub(crate) fn storage_iter_next(
+            iterator_id: u64,
+            key_register_id: u64,
+            value_register_id: u64,
+        ) -> u64;
         // ###############
         // # Validator API #
         // ###############
@@ -176,8 +185,93 @@ pub fn read_input_arr20() -> [u8; 20] {
     }
 }
 
+/// Raw key, outside of every namespace, of the storage namespace the engine
+/// serves.
+const NAMESPACE_KEY: &[u8; 10] = b"\0NAMESPACE";
+
+/// Longest storage namespace, so that its length fits in a byte.
+pub const MAX_NAMESPACE_LEN: usize = 255;
+
+/// Prefix of every storage key of the namespace the engine serves, read from
+/// storage on first use.
+///
+/// Several engine instances keep their state side by side under one account,
+/// each in its own namespace, and the owner switches which one the deployed
+/// code serves, see `set_namespace`. In a namespace every key is prefixed
+/// with `0xff`, the length of the namespace and the namespace; `0xff` is above
+/// every key prefix, which keeps the key ranges an instance iterates over free
+/// of other instances' keys. In the default, empty, namespace keys are left
+/// as they are.
+struct KeyPrefix(UnsafeCell<Option<Vec<u8>>>);
+
+// The contract runs single-threaded.
+unsafe impl Sync for KeyPrefix {}
+
+static KEY_PREFIX: KeyPrefix = KeyPrefix(UnsafeCell::new(None));
+
+fn key_prefix_of(namespace: &[u8]) -> Vec<u8> {
+    if namespace.is_empty() {
+        return Vec::new();
+    }
+    let mut prefix = Vec::with_capacity(2 + namespace.len());
+    prefix.push(0xff);
+    prefix.push(namespace.len() as u8);
+    prefix.extend_from_slice(namespace);
+    prefix
+}
+
+fn key_prefix() -> &'static [u8] {
+    let cached = unsafe { &mut *KEY_PREFIX.0.get() };
+    cached.get_or_insert_with(|| key_prefix_of(&get_namespace()))
+}
+
+/// Returns the storage namespace the engine serves, empty by default.
+pub fn get_namespace() -> Vec<u8> {
+    unsafe {
+        if exports::storage_read(NAMESPACE_KEY.len() as _, NAMESPACE_KEY.as_ptr() as _, 0) == 1 {
+            read_register(0)
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Makes every later storage access of this and following calls use
+/// `namespace`.
+pub fn set_namespace(namespace: &[u8]) {
+    if namespace.len() > MAX_NAMESPACE_LEN {
+        panic_utf8(b"ERR_NAMESPACE_TOO_LONG");
+    }
+    unsafe {
+        if namespace.is_empty() {
+            exports::storage_remove(NAMESPACE_KEY.len() as _, NAMESPACE_KEY.as_ptr() as _, 0);
+        } else {
+            exports::storage_write(
+                NAMESPACE_KEY.len() as _,
+                NAMESPACE_KEY.as_ptr() as _,
+                namespace.len() as _,
+                namespace.as_ptr() as _,
+                0,
+            );
+        }
+        *KEY_PREFIX.0.get() = Some(key_prefix_of(namespace));
+    }
+}
+
+fn namespaced(key: &[u8]) -> Cow<[u8]> {
+    let prefix = key_prefix();
+    if prefix.is_empty() {
+        return Cow::Borrowed(key);
+    }
+    let mut result = Vec::with_capacity(prefix.len() + key.len());
+    result.extend_from_slice(prefix);
+    result.extend_from_slice(key);
+    Cow::Owned(result)
+}
+
 /// Reads current input and stores in the given key keeping data in the runtime.
 pub fn read_input_and_store(key: &[u8]) {
+    let key = namespaced(key);
     unsafe {
         exports::input(0);
         // Store register 0 into key, store the previous value in register 1.
@@ -194,10 +288,15 @@ pub fn return_output(value: &[u8]) {
 
 #[allow(dead_code)]
 pub fn read_storage(key: &[u8]) -> Option<Vec<u8>> {
+    let key = namespaced(key);
     unsafe {
         if exports::storage_read(key.len() as u64, key.as_ptr() as u64, 0) == 1 {
-            let bytes: Vec<u8> = vec![0u8; exports::register_len(0) as usize];
-            exports::read_register(0, bytes.as_ptr() as *const u64 as u64);
+            // The register is copied straight into the new buffer, so there is
+            // no need to zero it first.
+            let len = exports::register_len(0) as usize;
+            let mut bytes: Vec<u8> = Vec::with_capacity(len);
+            exports::read_register(0, bytes.as_mut_ptr() as *const u64 as u64);
+            bytes.set_len(len);
             Some(bytes)
         } else {
             None
@@ -205,8 +304,57 @@ pub fn read_storage(key: &[u8]) -> Option<Vec<u8>> {
     }
 }
 
+/// Returns the length of the value stored under the key, leaving the value
+/// itself in the register instead of copying it into wasm memory.
+#[allow(dead_code)]
+pub fn read_storage_len(key: &[u8]) -> Option<usize> {
+    let key = namespaced(key);
+    unsafe {
+        if exports::storage_read(key.len() as u64, key.as_ptr() as u64, 0) == 1 {
+            Some(exports::register_len(0) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+fn read_register(register_id: u64) -> Vec<u8> {
+    unsafe {
+        let bytes: Vec<u8> = vec![0u8; exports::register_len(register_id) as usize];
+        exports::read_register(register_id, bytes.as_ptr() as *const u64 as u64);
+        bytes
+    }
+}
+
+/// Starts iterating over the keys in `start..end`, in lexicographic order.
+pub fn storage_iter_range(start: &[u8], end: &[u8]) -> u64 {
+    let start = namespaced(start);
+    let end = namespaced(end);
+    unsafe {
+        exports::storage_iter_range(
+            start.len() as u64,
+            start.as_ptr() as u64,
+            end.len() as u64,
+            end.as_ptr() as u64,
+        )
+    }
+}
+
+/// Returns the next key, without the namespace, and value of a storage
+/// iterator.
+pub fn storage_iter_next(iterator_id: u64) -> Option<(Vec<u8>, Vec<u8>)> {
+    if unsafe { exports::storage_iter_next(iterator_id, 0, 1) } == 1 {
+        let mut key = read_register(0);
+        key.drain(..key_prefix().len());
+        Some((key, read_register(1)))
+    } else {
+        None
+    }
+}
+
 /// Read u64 from storage at given key.
 pub fn read_u64(key: &[u8]) -> Option<u64> {
+    let key = namespaced(key);
     unsafe {
         if exports::storage_read(key.len() as u64, key.as_ptr() as u64, 0) == 1 {
             let result = [0u8; 8];
@@ -220,6 +368,7 @@ pub fn read_u64(key: &[u8]) -> Option<u64> {
 
 #[allow(dead_code)]
 pub fn write_storage(key: &[u8], value: &[u8]) {
+    let key = namespaced(key);
     unsafe {
         exports::storage_write(
             key.len() as u64,
@@ -233,6 +382,7 @@ pub fn write_storage(key: &[u8], value: &[u8]) {
 
 #[allow(dead_code)]
 pub fn remove_storage(key: &[u8]) {
+    let key = namespaced(key);
     unsafe {
         exports::storage_remove(key.len() as u64, key.as_ptr() as u64, 0);
     }
@@ -248,6 +398,11 @@ pub fn block_index() -> u64 {
     unsafe { exports::block_index() }
 }
 
+#[allow(dead_code)]
+pub fn epoch_height() -> u64 {
+    unsafe { exports::epoch_height() }
+}
+
 #[allow(dead_code)]
 pub fn panic() {
     unsafe { exports::panic() }
@@ -318,16 +473,37 @@ pub fn current_account_id() -> Vec<u8> {
     }
 }
 
-/// Deploy code from given key in place of the current key.
-pub fn self_deploy(code_key: &[u8]) {
+/// Deploy code from given key in place of the current code, then call `method`
+/// of the new code with all the prepaid gas left but `reserved_gas`, which
+/// is kept for finishing the current call.
+///
+/// The code stays under the key: if `method` fails, the deployment is
+/// reverted with it and can be retried.
+pub fn self_deploy(code_key: &[u8], method: &[u8], reserved_gas: u64) {
+    let code_key = namespaced(code_key);
     unsafe {
         // Load current account id into register 0.
         exports::current_account_id(0);
         // Use register 0 as the destination for the promise.
         let promise_id = exports::promise_batch_create(u64::MAX as _, 0);
-        // Remove code from storage and store it in register 1.
-        exports::storage_remove(code_key.len() as _, code_key.as_ptr() as _, 1);
+        // Read code from storage into register 1.
+        if exports::storage_read(code_key.len() as _, code_key.as_ptr() as _, 1) == 0 {
+            panic_utf8(b"ERR_NO_UPGRADE");
+        }
         exports::promise_batch_action_deploy_contract(promise_id, u64::MAX as _, 1);
+        let gas = prepaid_gas()
+            .saturating_sub(used_gas())
+            .saturating_sub(reserved_gas);
+        let amount = 0u128;
+        exports::promise_batch_action_function_call(
+            promise_id,
+            method.len() as _,
+            method.as_ptr() as _,
+            0,
+            0,
+            &amount as *const u128 as _,
+            gas,
+        );
     }
 }
 
@@ -357,6 +533,11 @@ pub fn prepaid_gas() -> u64 {
     unsafe { exports::prepaid_gas() }
 }
 
+#[allow(dead_code)]
+pub fn used_gas() -> u64 {
+    unsafe { exports::used_gas() }
+}
+
 #[allow(dead_code)]
 pub fn promise_create(
     account_id: String,
@@ -477,6 +658,7 @@ pub fn promise_batch_create(account_id: String) -> u64 {
 }
 
 #[allow(dead_code)]
-pub fn storage_has_key(key: &str) -> bool {
+pub fn storage_has_key(key: &[u8]) -> bool {
+    let key = namespaced(key);
     unsafe { exports::storage_has_key(key.len() as u64, key.as_ptr() as u64) == 1 }
 }
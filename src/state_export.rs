@@ -0,0 +1,123 @@
+//! Chunked export and import of the EVM state, for re-genesis and disaster
+//! recovery.
+//!
+//! `export` walks the account keys in order, `limit` entries at a time, and
+//! returns each chunk with a manifest hash chained over all chunks so far.
+//! `import` writes the chunks into a fresh deployment and checks the same
+//! chain, so a missing, reordered or altered chunk is rejected, and the final
+//! manifests of both sides match once everything is imported.
+//!
+//! Engine configuration (the keys with the `0x00` prefix) is not exported, the
+//! new deployment gets its own with `new`. `import` rejects it, and any key
+//! that is not an account record.
+//!
+//! `hash` computes the same kind of manifest over all keys, configuration
+//! included, leaving out the keys under given prefixes. Hashing the state
+//! before an upgrade and after its migration, leaving out the keys the
+//! migration is meant to change, shows that nothing else changed. The upgrade
+//! itself changes `\0CODE` and `\0STATE_VERSION`.
+
+use borsh::BorshSerialize;
+
+use crate::parameters::{ExportStateArgs, HashStateArgs, StateChunk, StateHash, StorageKeyKind};
+use crate::prelude::Vec;
+use crate::sdk;
+use crate::storage::parse_key;
+use crate::types::{keccak, RawH256};
+
+const IMPORT_MANIFEST_KEY: &[u8; 16] = b"\0IMPORT_MANIFEST";
+
+/// First key after the configuration keys.
+const START_KEY: &[u8] = &[0x1];
+/// Exclusive end of the exported keys, above every key prefix.
+const END_KEY: &[u8] = &[0xff];
+
+fn chain_manifest(previous: &RawH256, entries: &[(Vec<u8>, Vec<u8>)]) -> RawH256 {
+    let entries_hash = keccak(&entries.try_to_vec().expect("ERR_SER"));
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(previous);
+    bytes[32..].copy_from_slice(entries_hash.as_bytes());
+    keccak(&bytes).0
+}
+
+pub fn export(args: ExportStateArgs) -> StateChunk {
+    if args.limit == 0 {
+        sdk::panic_utf8(b"ERR_ZERO_LIMIT");
+    }
+    let start = if args.from.as_slice() < START_KEY {
+        START_KEY
+    } else {
+        args.from.as_slice()
+    };
+
+    let iterator = sdk::storage_iter_range(start, END_KEY);
+    let mut entries = Vec::new();
+    let mut next = None;
+    while let Some((key, value)) = sdk::storage_iter_next(iterator) {
+        if entries.len() == args.limit as usize {
+            next = Some(key);
+            break;
+        }
+        entries.push((key, value));
+    }
+
+    let manifest = chain_manifest(&args.manifest, &entries);
+    StateChunk {
+        entries,
+        next,
+        manifest,
+    }
+}
+
+pub fn hash(args: HashStateArgs) -> StateHash {
+    if args.limit == 0 {
+        sdk::panic_utf8(b"ERR_ZERO_LIMIT");
+    }
+
+    let iterator = sdk::storage_iter_range(&args.from, END_KEY);
+    let mut entries = Vec::new();
+    let mut visited = 0;
+    let mut next = None;
+    while let Some((key, value)) = sdk::storage_iter_next(iterator) {
+        if visited == args.limit {
+            next = Some(key);
+            break;
+        }
+        visited += 1;
+        if !args.exclude.iter().any(|prefix| key.starts_with(prefix)) {
+            entries.push((key, value));
+        }
+    }
+
+    StateHash {
+        next,
+        manifest: chain_manifest(&args.manifest, &entries),
+    }
+}
+
+/// Writes the chunk and returns the manifest hash so far.
+pub fn import(chunk: StateChunk) -> RawH256 {
+    let mut previous = [0u8; 32];
+    if let Some(manifest) = sdk::read_storage(IMPORT_MANIFEST_KEY) {
+        previous.copy_from_slice(&manifest);
+    }
+    if chain_manifest(&previous, &chunk.entries) != chunk.manifest {
+        sdk::panic_utf8(b"ERR_MANIFEST_MISMATCH");
+    }
+
+    for (key, value) in &chunk.entries {
+        // Only the account records `export` reads may be written, nothing
+        // outside the key prefixes of `storage`.
+        match parse_key(key) {
+            StorageKeyKind::Config(_) => sdk::panic_utf8(b"ERR_CONFIG_KEY"),
+            StorageKeyKind::Unknown => sdk::panic_utf8(b"ERR_UNKNOWN_KEY"),
+            _ => {}
+        }
+        if sdk::storage_has_key(key) {
+            sdk::panic_utf8(b"ERR_KEY_EXISTS");
+        }
+        sdk::write_storage(key, value);
+    }
+    sdk::write_storage(IMPORT_MANIFEST_KEY, &chunk.manifest);
+    chunk.manifest
+}
@@ -1,3 +1,4 @@
+use crate::parameters::StorageKeyKind;
 use crate::prelude::{Address, H256};
 
 #[allow(dead_code)]
@@ -7,6 +8,19 @@ pub enum KeyPrefix {
     Balance = 0x2,
     Code = 0x3,
     Storage = 0x4,
+    /// Keccak hash of an address's code, the code itself is under `Code`
+    /// keyed by that hash.
+    CodeHash = 0x5,
+    /// How many times the storage of an address has been cleared.
+    Generation = 0x6,
+    /// Bytes of NEAR storage used by the records of an address.
+    StorageUsage = 0x7,
+    /// Per-block checkpoint, keyed by the big-endian block height so that
+    /// checkpoints are in block order.
+    BlockCheckpoint = 0x8,
+    /// Code too long for a single value: the length of the code keyed by its
+    /// hash, and the chunks of the code keyed by the hash and their index.
+    CodeChunk = 0x9,
 }
 
 #[allow(dead_code)]
@@ -18,13 +32,244 @@ pub fn address_to_key(prefix: KeyPrefix, address: &Address) -> [u8; 21] {
 }
 
 #[allow(dead_code)]
-pub fn storage_to_key(address: &Address, key: &H256) -> [u8; 53] {
-    let mut result = [0u8; 53];
-    result[0] = KeyPrefix::Storage as u8;
-    result[1..21].copy_from_slice(&address.0);
-    result[21..].copy_from_slice(&key.0);
+pub fn hash_to_key(prefix: KeyPrefix, hash: &H256) -> [u8; 33] {
+    let mut result = [0u8; 33];
+    result[0] = prefix as u8;
+    result[1..].copy_from_slice(&hash.0);
+    result
+}
+
+#[allow(dead_code)]
+pub fn block_to_key(height: u64) -> [u8; 9] {
+    let mut result = [0u8; 9];
+    result[0] = KeyPrefix::BlockCheckpoint as u8;
+    result[1..].copy_from_slice(&height.to_be_bytes());
+    result
+}
+
+#[allow(dead_code)]
+pub fn code_chunk_key(code_hash: &H256, index: u32) -> [u8; 37] {
+    let mut result = [0u8; 37];
+    result[0] = KeyPrefix::CodeChunk as u8;
+    result[1..33].copy_from_slice(&code_hash.0);
+    result[33..].copy_from_slice(&index.to_be_bytes());
     result
 }
 
+/// Tells what a raw storage key refers to, the inverse of the key functions
+/// above.
+#[allow(dead_code)]
+pub fn parse_key(key: &[u8]) -> StorageKeyKind {
+    fn array20(bytes: &[u8]) -> [u8; 20] {
+        let mut result = [0u8; 20];
+        result.copy_from_slice(bytes);
+        result
+    }
+
+    fn array32(bytes: &[u8]) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        result.copy_from_slice(bytes);
+        result
+    }
+
+    let (prefix, rest) = match key.split_first() {
+        Some(split) => split,
+        None => return StorageKeyKind::Unknown,
+    };
+    match (*prefix, rest.len()) {
+        (0x0, _) => StorageKeyKind::Config(rest.to_vec()),
+        (0x1, 20) => StorageKeyKind::Nonce(array20(rest)),
+        (0x2, 20) => StorageKeyKind::Balance(array20(rest)),
+        (0x3, 20) => StorageKeyKind::CodeByAddress(array20(rest)),
+        (0x3, 32) => StorageKeyKind::CodeByHash(array32(rest)),
+        (0x4, 52) => StorageKeyKind::Storage {
+            address: array20(&rest[..20]),
+            generation: 0,
+            slot: array32(&rest[20..]),
+        },
+        (0x4, 56) => {
+            let mut generation = [0u8; 4];
+            generation.copy_from_slice(&rest[20..24]);
+            StorageKeyKind::Storage {
+                address: array20(&rest[..20]),
+                generation: u32::from_le_bytes(generation),
+                slot: array32(&rest[24..]),
+            }
+        }
+        (0x5, 20) => StorageKeyKind::CodeHash(array20(rest)),
+        (0x6, 20) => StorageKeyKind::Generation(array20(rest)),
+        (0x7, 20) => StorageKeyKind::StorageUsage(array20(rest)),
+        (0x8, 8) => {
+            let mut height = [0u8; 8];
+            height.copy_from_slice(rest);
+            StorageKeyKind::BlockCheckpoint(u64::from_be_bytes(height))
+        }
+        (0x9, 32) => StorageKeyKind::CodeChunkHead(array32(rest)),
+        (0x9, 36) => {
+            let mut index = [0u8; 4];
+            index.copy_from_slice(&rest[32..]);
+            StorageKeyKind::CodeChunk {
+                code_hash: array32(&rest[..32]),
+                index: u32::from_be_bytes(index),
+            }
+        }
+        _ => StorageKeyKind::Unknown,
+    }
+}
+
+/// Trims the leading zero bytes of a big-endian value before it is stored.
+#[allow(dead_code)]
+pub fn trim_value(value: &[u8]) -> &[u8] {
+    let start = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    &value[start..]
+}
+
+/// Left-pads a stored big-endian value back to 32 bytes. Values stored before
+/// trimming was introduced are already 32 bytes long.
+#[allow(dead_code)]
+pub fn pad_value(value: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    result[32 - value.len()..].copy_from_slice(value);
+    result
+}
+
+/// Storage keys of a single address and storage generation, built in one
+/// buffer where only the slot part changes between keys.
+///
+/// Generation 0 keys are `prefix ++ address ++ slot`, the layout from before
+/// generations existed, later generations have the generation in between.
+#[allow(dead_code)]
+pub struct StorageKeys {
+    buffer: [u8; 57],
+    slot_start: usize,
+}
+
+#[allow(dead_code)]
+impl StorageKeys {
+    pub fn new(address: &Address, generation: u32) -> Self {
+        let mut buffer = [0u8; 57];
+        buffer[0] = KeyPrefix::Storage as u8;
+        buffer[1..21].copy_from_slice(&address.0);
+        let slot_start = if generation == 0 {
+            21
+        } else {
+            buffer[21..25].copy_from_slice(&generation.to_le_bytes());
+            25
+        };
+        Self { buffer, slot_start }
+    }
+
+    pub fn key(&mut self, key: &H256) -> &[u8] {
+        let end = self.slot_start + 32;
+        self.buffer[self.slot_start..end].copy_from_slice(&key.0);
+        &self.buffer[..end]
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_and_pad_value() {
+        for value in &[
+            H256::zero(),
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(0x0100),
+            H256::repeat_byte(0xff),
+        ] {
+            let trimmed = trim_value(&value.0);
+            assert!(trimmed.first() != Some(&0));
+            assert_eq!(&pad_value(trimmed), &value.0);
+            assert_eq!(&pad_value(&value.0), &value.0);
+        }
+        assert_eq!(trim_value(&H256::from_low_u64_be(0x0100).0), &[1u8, 0u8]);
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let address = Address::from_low_u64_be(0x1234);
+        let hash = H256::repeat_byte(0xab);
+
+        assert_eq!(
+            parse_key(b"\0STATE"),
+            StorageKeyKind::Config(b"STATE".to_vec())
+        );
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::Nonce, &address)),
+            StorageKeyKind::Nonce(address.0)
+        );
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::Balance, &address)),
+            StorageKeyKind::Balance(address.0)
+        );
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::Code, &address)),
+            StorageKeyKind::CodeByAddress(address.0)
+        );
+        assert_eq!(
+            parse_key(&hash_to_key(KeyPrefix::Code, &hash)),
+            StorageKeyKind::CodeByHash(hash.0)
+        );
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::CodeHash, &address)),
+            StorageKeyKind::CodeHash(address.0)
+        );
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::Generation, &address)),
+            StorageKeyKind::Generation(address.0)
+        );
+        for generation in &[0, 7] {
+            assert_eq!(
+                parse_key(StorageKeys::new(&address, *generation).key(&hash)),
+                StorageKeyKind::Storage {
+                    address: address.0,
+                    generation: *generation,
+                    slot: hash.0,
+                }
+            );
+        }
+        assert_eq!(
+            parse_key(&address_to_key(KeyPrefix::StorageUsage, &address)),
+            StorageKeyKind::StorageUsage(address.0)
+        );
+        assert_eq!(
+            parse_key(&block_to_key(1234)),
+            StorageKeyKind::BlockCheckpoint(1234)
+        );
+        assert_eq!(
+            parse_key(&hash_to_key(KeyPrefix::CodeChunk, &hash)),
+            StorageKeyKind::CodeChunkHead(hash.0)
+        );
+        assert_eq!(
+            parse_key(&code_chunk_key(&hash, 3)),
+            StorageKeyKind::CodeChunk {
+                code_hash: hash.0,
+                index: 3,
+            }
+        );
+        assert_eq!(parse_key(&[]), StorageKeyKind::Unknown);
+        assert_eq!(parse_key(&[0x1, 0x2]), StorageKeyKind::Unknown);
+        assert_eq!(parse_key(&[0xa; 21]), StorageKeyKind::Unknown);
+    }
+
+    #[test]
+    fn test_storage_keys() {
+        let address = Address::from_low_u64_be(0x1234);
+        let index = H256::repeat_byte(0xff);
+
+        let mut expected = vec![KeyPrefix::Storage as u8];
+        expected.extend_from_slice(&address.0);
+        expected.extend_from_slice(&index.0);
+        let mut keys = StorageKeys::new(&address, 0);
+        assert_eq!(keys.key(&H256::zero()).len(), 53);
+        assert_eq!(keys.key(&index), &expected[..]);
+
+        let mut expected = vec![KeyPrefix::Storage as u8];
+        expected.extend_from_slice(&address.0);
+        expected.extend_from_slice(&[1, 0, 0, 0]);
+        expected.extend_from_slice(&index.0);
+        let mut keys = StorageKeys::new(&address, 1);
+        assert_eq!(keys.key(&index), &expected[..]);
+    }
+}
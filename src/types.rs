@@ -43,6 +43,23 @@ pub enum NonceError {
     IncorrectNonce,
 }
 
+/// Errors checking a transaction against the limits the owner configured
+pub enum LimitError {
+    /// The transaction declares more gas than a single transaction may use
+    GasLimitTooHigh,
+    /// The allowlist is on and the relayer is not on it
+    RelayerNotAllowed,
+    /// The relayer already submitted its quota of transactions in this block
+    RelayerQuotaExceeded,
+    /// The gas price is below the minimum
+    GasPriceTooLow,
+    /// The gas price is above the maximum
+    GasPriceTooHigh,
+    /// The block already has as many transactions as allowed, retry in a
+    /// later block
+    BlockLimitReached,
+}
+
 pub type Result<T> = core::result::Result<T, ErrorKind>;
 
 #[allow(dead_code)]
@@ -69,12 +86,29 @@ const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
 
 #[allow(dead_code)]
 pub fn bytes_to_hex(v: &[u8]) -> String {
-    let mut result = String::new();
+    let mut result = String::with_capacity(2 * v.len());
+    push_hex(&mut result, v);
+    result
+}
+
+/// Same as `bytes_to_hex(&log_to_bytes(log))`, but writes the hex directly
+/// into a single, pre-sized string.
+#[allow(dead_code)]
+pub fn log_to_hex(log: &Log) -> String {
+    let mut result = String::with_capacity(2 * (1 + log.topics.len() * 32 + log.data.len()));
+    push_hex(&mut result, &[log.topics.len() as u8]);
+    for topic in log.topics.iter() {
+        push_hex(&mut result, &topic.0);
+    }
+    push_hex(&mut result, &log.data);
+    result
+}
+
+fn push_hex(result: &mut String, v: &[u8]) {
     for x in v {
         result.push(HEX_ALPHABET[(x / 16) as usize] as char);
         result.push(HEX_ALPHABET[(x % 16) as usize] as char);
     }
-    result
 }
 
 #[cfg(feature = "contract")]
@@ -105,4 +139,21 @@ mod tests {
             "0001ff10".to_string()
         );
     }
+
+    #[test]
+    fn test_log_to_hex() {
+        let log = Log {
+            address: Address::from_low_u64_be(1),
+            topics: vec![H256::from_low_u64_be(2), H256::repeat_byte(0xab)],
+            data: vec![0u8, 1u8, 255u8],
+        };
+        assert_eq!(log_to_hex(&log), bytes_to_hex(&log_to_bytes(log.clone())));
+
+        let log = Log {
+            address: Address::zero(),
+            topics: vec![],
+            data: vec![],
+        };
+        assert_eq!(log_to_hex(&log), "00".to_string());
+    }
 }
@@ -0,0 +1,285 @@
+//! Shared fixtures for the integration tests.
+#![allow(dead_code)]
+
+use near_crypto::{PublicKey, Signature, Signer};
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::test_utils::accounts;
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+
+use aurora_engine::meta_parsing::{near_erc712_domain, prepare_meta_call_args};
+use aurora_engine::parameters::{
+    ExportStateArgs, FunctionCallArgs, MetaCallArgs, NewCallArgs, StateChunk, StorageKeyKind,
+};
+use aurora_engine::prelude::{Address, U256};
+use aurora_engine::transaction::{EthSignedTransaction, EthTransaction};
+use aurora_engine::types::{keccak, u256_to_arr, InternalMetaCallArgs, RawH256};
+
+near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
+    pub EVM_WASM_BYTES => "release.wasm",
+    // A second build of the engine, to upgrade to.
+    pub EVM_DEBUG_WASM_BYTES => "debug.wasm",
+    // The engine with one more migration, see `migration_test`.
+    pub EVM_MIGRATION_TEST_WASM_BYTES => "migration-test.wasm"
+}
+
+/// Account id the engine is deployed to.
+pub fn contract_id() -> String {
+    accounts(0).to_string()
+}
+
+/// Deploys the engine and calls `new` on it, with the simulator's master
+/// account as the owner.
+///
+/// Returns the master account and the engine contract account.
+pub fn deploy_evm() -> (UserAccount, UserAccount) {
+    deploy_evm_with_args(|master_account| NewCallArgs {
+        chain_id: [0u8; 32],
+        owner_id: master_account.account_id.clone(),
+        bridge_prover_id: contract_id(),
+        upgrade_delay_blocks: 1,
+    })
+}
+
+/// Same as `deploy_evm`, but lets the caller build the `new` arguments.
+pub fn deploy_evm_with_args<F>(make_args: F) -> (UserAccount, UserAccount)
+where
+    F: FnOnce(&UserAccount) -> NewCallArgs,
+{
+    let master_account = near_sdk_sim::init_simulator(None);
+    let contract_account = master_account.deploy(*EVM_WASM_BYTES, contract_id(), to_yocto("1000"));
+    contract_account
+        .call(
+            contract_id(),
+            "new",
+            &make_args(&master_account).try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            STORAGE_AMOUNT,
+        )
+        .assert_success();
+    (master_account, contract_account)
+}
+
+/// Deploys EVM bytecode with `account` as the NEAR predecessor.
+///
+/// Returns the address of the new EVM contract.
+pub fn deploy_code(account: &UserAccount, code: &[u8]) -> Address {
+    let result = account.call(contract_id(), "deploy_code", code, DEFAULT_GAS, 0);
+    result.assert_success();
+    Address::from_slice(&result.unwrap())
+}
+
+/// Calls an EVM contract through the engine's `call` method, with `account`
+/// as the NEAR predecessor and `deposit` yoctoNEAR attached.
+pub fn call_as(
+    account: &UserAccount,
+    contract: Address,
+    input: Vec<u8>,
+    deposit: u128,
+) -> ExecutionResult {
+    let args = FunctionCallArgs {
+        contract: contract.0,
+        input,
+    };
+    account.call(
+        contract_id(),
+        "call",
+        &args.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        deposit,
+    )
+}
+
+/// Stages `wasm` as the next engine code, with `owner` as the predecessor.
+pub fn stage_upgrade(owner: &UserAccount, wasm: &[u8]) -> ExecutionResult {
+    owner.call(contract_id(), "stage_upgrade", wasm, DEFAULT_GAS, 0)
+}
+
+/// Deploys the staged engine code, with `owner` as the predecessor.
+pub fn deploy_upgrade(owner: &UserAccount) -> ExecutionResult {
+    owner.call(contract_id(), "deploy_upgrade", &[], DEFAULT_GAS, 0)
+}
+
+/// Produces blocks until the staged upgrade is past its delay.
+pub fn wait_for_upgrade(account: &UserAccount) {
+    let result = account
+        .view(contract_id(), "get_upgrade_index", &[])
+        .unwrap();
+    let mut index = [0u8; 8];
+    index.copy_from_slice(&result);
+    let index = u64::from_le_bytes(index);
+    let height = account.borrow_runtime().current_block().block_height;
+    if height <= index {
+        account
+            .borrow_runtime_mut()
+            .produce_blocks(index - height + 1)
+            .unwrap();
+    }
+}
+
+/// Swaps the deployed engine code for `wasm` mid-test the way an owner
+/// would: stages it, waits out the upgrade delay and deploys it. The engine
+/// state is kept, so the test can go on using it.
+pub fn upgrade_evm(owner: &UserAccount, wasm: &[u8]) {
+    stage_upgrade(owner, wasm).assert_success();
+    wait_for_upgrade(owner);
+    deploy_upgrade(owner).assert_success();
+}
+
+/// Reads every raw EVM account record of the engine, in key order, through
+/// `export_state` with the owner `owner` as the predecessor.
+pub fn raw_state(owner: &UserAccount) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let mut from = Vec::new();
+    let mut manifest = [0u8; 32];
+    loop {
+        let args = ExportStateArgs {
+            from,
+            limit: 100,
+            manifest,
+        };
+        let result = owner.call(
+            contract_id(),
+            "export_state",
+            &args.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        );
+        result.assert_success();
+        let chunk = StateChunk::try_from_slice(&result.unwrap()).unwrap();
+        entries.extend(chunk.entries);
+        manifest = chunk.manifest;
+        match chunk.next {
+            Some(key) => from = key,
+            None => return entries,
+        }
+    }
+}
+
+/// Tells what a raw engine storage key refers to, see `parse_storage_key`.
+pub fn parse_storage_key(account: &UserAccount, key: &[u8]) -> StorageKeyKind {
+    let result = account
+        .view(contract_id(), "parse_storage_key", key)
+        .unwrap();
+    StorageKeyKind::try_from_slice(&result).unwrap()
+}
+
+/// Returns the storage generation of an address, which moves on every time
+/// the account self-destructs or is created over; 0 while it never has.
+pub fn storage_generation(owner: &UserAccount, address: Address) -> u32 {
+    for (key, value) in raw_state(owner) {
+        if parse_storage_key(owner, &key) == StorageKeyKind::Generation(address.0) {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&value);
+            return u32::from_le_bytes(bytes);
+        }
+    }
+    0
+}
+
+/// Returns the storage slots of an address as `(generation, slot, value)`,
+/// in every generation: slots of earlier generations stay stored until
+/// `cleanup` removes them, but the engine no longer reads them.
+pub fn raw_storage_slots(owner: &UserAccount, address: Address) -> Vec<(u32, RawH256, Vec<u8>)> {
+    raw_state(owner)
+        .into_iter()
+        .filter_map(|(key, value)| match parse_storage_key(owner, &key) {
+            StorageKeyKind::Storage {
+                address: slot_address,
+                generation,
+                slot,
+            } if slot_address == address.0 => Some((generation, slot, value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Signs a meta-call the way eth-sig-util does and returns the borsh-encoded
+/// `meta_call` arguments.
+pub fn encode_meta_call_function_args(
+    signer: &dyn Signer,
+    chain_id: u64,
+    account_id: &str,
+    nonce: U256,
+    fee_amount: U256,
+    fee_address: Address,
+    contract_address: Address,
+    value: U256,
+    method_def: &str,
+    args: Vec<u8>,
+) -> Vec<u8> {
+    let domain_separator = near_erc712_domain(U256::from(chain_id));
+    let (msg, _) = match prepare_meta_call_args(
+        &domain_separator,
+        account_id.as_bytes(),
+        method_def.to_string(),
+        &InternalMetaCallArgs {
+            sender: Address::zero(),
+            nonce,
+            fee_amount,
+            fee_address,
+            contract_address,
+            value,
+            input: args.clone(),
+        },
+    ) {
+        Ok(x) => x,
+        Err(_) => panic!("Failed to prepare"),
+    };
+    match signer.sign(&msg) {
+        Signature::ED25519(_) => panic!("Wrong Signer"),
+        Signature::SECP256K1(sig) => {
+            let array = Into::<[u8; 65]>::into(sig.clone()).to_vec();
+            let mut signature = [0u8; 64];
+            signature.copy_from_slice(&array[..64]);
+            MetaCallArgs {
+                signature,
+                // Add 27 to align eth-sig-util signature format
+                v: 27,
+                nonce: u256_to_arr(&nonce),
+                fee_amount: u256_to_arr(&fee_amount),
+                fee_address: fee_address.0,
+                contract_address: contract_address.0,
+                value: u256_to_arr(&value),
+                method_def: method_def.to_string(),
+                args,
+            }
+            .try_to_vec()
+            .expect("Failed to serialize")
+        }
+    }
+}
+
+/// Signs a transaction without a chain id and returns its RLP encoding, the
+/// input of `raw_call`.
+pub fn sign_transaction(signer: &dyn Signer, transaction: EthTransaction) -> Vec<u8> {
+    let mut stream = rlp::RlpStream::new();
+    transaction.rlp_append_unsigned(&mut stream, None);
+    let message_hash = keccak(stream.as_raw());
+    match signer.sign(message_hash.as_bytes()) {
+        Signature::ED25519(_) => panic!("Wrong Signer"),
+        Signature::SECP256K1(sig) => {
+            let array: [u8; 65] = sig.into();
+            let signed_transaction = EthSignedTransaction {
+                transaction,
+                v: 27 + u64::from(array[64]),
+                r: U256::from_big_endian(&array[..32]),
+                s: U256::from_big_endian(&array[32..64]),
+            };
+            rlp::encode(&signed_transaction).to_vec()
+        }
+    }
+}
+
+/// Ethereum address of a SECP256K1 public key.
+pub fn public_key_to_address(public_key: PublicKey) -> Address {
+    match public_key {
+        PublicKey::ED25519(_) => panic!("Wrong PublicKey"),
+        PublicKey::SECP256K1(pubkey) => {
+            let pk: [u8; 64] = pubkey.into();
+            let bytes = keccak(&pk.to_vec());
+            let mut result = Address::zero();
+            result.as_bytes_mut().copy_from_slice(&bytes[12..]);
+            result
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::AccountOverrideArgs;
+use aurora_engine::prelude::{Address, U256};
+use aurora_engine::types::u256_to_arr;
+
+mod common;
+
+use common::{contract_id, deploy_evm};
+
+fn get_balance(account: &UserAccount, address: Address) -> U256 {
+    let result = account
+        .view(contract_id(), "get_balance", address.as_bytes())
+        .unwrap();
+    U256::from_big_endian(&result)
+}
+
+fn get_account_override(account: &UserAccount) -> Option<AccountOverrideArgs> {
+    let result = account
+        .view(contract_id(), "get_account_override", &[])
+        .unwrap();
+    Option::<AccountOverrideArgs>::try_from_slice(&result).unwrap()
+}
+
+#[test]
+fn test_account_override() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let address = Address::from_low_u64_be(0x1234);
+    let args = AccountOverrideArgs {
+        address: address.0,
+        balance: Some(u256_to_arr(&U256::from(1_000))),
+        nonce: None,
+        justification_hash: [0xaa; 32],
+    }
+    .try_to_vec()
+    .unwrap();
+    let call = |account: &UserAccount, method: &str, input: &[u8]| {
+        account.call(contract_id(), method, input, DEFAULT_GAS, 0)
+    };
+
+    assert!(!call(&alice, "stage_account_override", &args).is_ok());
+    call(&master_account, "stage_account_override", &args).assert_success();
+    assert_eq!(
+        get_account_override(&alice).unwrap().justification_hash,
+        [0xaa; 32]
+    );
+    // Only one override at a time.
+    assert!(!call(&master_account, "stage_account_override", &args).is_ok());
+
+    assert!(!call(&alice, "apply_account_override", &[]).is_ok());
+    assert_eq!(get_balance(&alice, address), U256::zero());
+    call(&master_account, "apply_account_override", &[]).assert_success();
+    assert_eq!(get_balance(&alice, address), U256::from(1_000));
+    assert!(get_account_override(&alice).is_none());
+}
@@ -0,0 +1,41 @@
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::{AuditLogEntry, GetAuditLogArgs};
+use aurora_engine::types::keccak;
+
+mod common;
+
+use common::{contract_id, deploy_evm};
+
+fn get_audit_log(account: &UserAccount, from: u64, limit: u64) -> Vec<AuditLogEntry> {
+    let args = GetAuditLogArgs { from, limit };
+    let result = account
+        .view(contract_id(), "get_audit_log", &args.try_to_vec().unwrap())
+        .unwrap();
+    Vec::<AuditLogEntry>::try_from_slice(&result).unwrap()
+}
+
+#[test]
+fn test_admin_actions_are_logged() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let input = 100_000u64.try_to_vec().unwrap();
+
+    // Calls failing the access check are not logged.
+    let result = alice.call(contract_id(), "set_tx_gas_limit", &input, DEFAULT_GAS, 0);
+    assert!(!result.is_ok());
+    master_account
+        .call(contract_id(), "set_tx_gas_limit", &input, DEFAULT_GAS, 0)
+        .assert_success();
+
+    let entries = get_audit_log(&alice, 0, 10);
+    let methods: Vec<_> = entries.iter().map(|entry| entry.method.as_str()).collect();
+    assert_eq!(methods, vec!["new", "set_tx_gas_limit"]);
+    assert_eq!(entries[1].caller, master_account.account_id);
+    assert_eq!(entries[1].input_hash, keccak(&input).0);
+    assert!(entries[0].block_height <= entries[1].block_height);
+
+    assert_eq!(get_audit_log(&alice, 1, 10).as_slice(), &entries[1..]);
+    assert!(get_audit_log(&alice, 2, 10).is_empty());
+}
@@ -0,0 +1,55 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::GetStorageAtArgs;
+use aurora_engine::prelude::Address;
+
+mod common;
+
+use common::{call_as, contract_id, deploy_code, deploy_evm};
+
+/// Init code that stores 42 in slot 0 and deploys a contract which
+/// self-destructs when called, with runtime code `CALLER SELFDESTRUCT`.
+const STORE_AND_SELFDESTRUCT: &str = "602a6000556002601160003960026000f333ff";
+
+fn cleanup(account: &UserAccount, limit: u32) -> u32 {
+    let result = account.call(
+        contract_id(),
+        "cleanup",
+        &limit.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    );
+    result.assert_success();
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&result.unwrap());
+    u32::from_le_bytes(bytes)
+}
+
+fn slot_zero(account: &UserAccount, address: Address) -> Vec<u8> {
+    let args = GetStorageAtArgs {
+        address: address.0,
+        key: [0u8; 32],
+    };
+    account
+        .view(contract_id(), "get_storage_at", &args.try_to_vec().unwrap())
+        .unwrap()
+}
+
+#[test]
+fn test_cleanup_selfdestructed_storage() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let contract = deploy_code(&alice, &hex::decode(STORE_AND_SELFDESTRUCT).unwrap());
+    assert_eq!(slot_zero(&alice, contract)[31], 42);
+
+    // Nothing to remove while the contract is alive.
+    assert_eq!(cleanup(&alice, 100), 0);
+
+    call_as(&alice, contract, Vec::new(), 0).assert_success();
+    assert_eq!(slot_zero(&alice, contract), vec![0u8; 32]);
+
+    // Anyone can clean up, and the slot is only removed once.
+    assert_eq!(cleanup(&alice, 100), 1);
+    assert_eq!(cleanup(&alice, 100), 0);
+}
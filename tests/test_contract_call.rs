@@ -0,0 +1,112 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::prelude::Address;
+use aurora_engine::types::near_account_to_evm_address;
+
+mod common;
+
+use common::{call_as, contract_id, deploy_code, deploy_evm};
+
+/// Init code for a contract that returns `CALLER` as a 32-byte word.
+///
+/// The runtime code is `CALLER PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN`.
+const CALLER_ECHO: &str = "6009600c60003960096000f33360005260206000f3";
+
+#[test]
+fn test_call_sender_is_predecessor_address() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let bob = master_account.create_user("bob.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&alice, &hex::decode(CALLER_ECHO).unwrap());
+
+    for account in &[&alice, &bob] {
+        let result = call_as(account, echo, Vec::new(), 0);
+        result.assert_success();
+        let output = result.unwrap();
+        assert_eq!(
+            Address::from_slice(&output[12..]),
+            near_account_to_evm_address(account.account_id.as_bytes())
+        );
+    }
+}
+
+#[test]
+fn test_call_with_attached_deposit() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&alice, &hex::decode(CALLER_ECHO).unwrap());
+
+    // The deposit is accepted, and the call runs as without one.
+    let result = call_as(&alice, echo, Vec::new(), to_yocto("1"));
+    result.assert_success();
+    let output = result.unwrap();
+    assert_eq!(
+        Address::from_slice(&output[12..]),
+        near_account_to_evm_address(alice.account_id.as_bytes())
+    );
+}
+
+#[test]
+fn test_deployed_contract_storage_usage() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&alice, &hex::decode(CALLER_ECHO).unwrap());
+
+    let usage = alice
+        .view(contract_id(), "get_storage_usage", echo.as_bytes())
+        .unwrap();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&usage);
+    // At least the code itself.
+    assert!(u64::from_le_bytes(bytes) >= 9);
+}
+
+#[test]
+fn test_call_tx_gas_limit() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&alice, &hex::decode(CALLER_ECHO).unwrap());
+
+    let set_tx_gas_limit = |account: &UserAccount, gas_limit: u64| {
+        account.call(
+            contract_id(),
+            "set_tx_gas_limit",
+            &gas_limit.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+    };
+    assert!(!set_tx_gas_limit(&alice, 21_000).is_ok());
+
+    // Not even enough for the intrinsic gas and the call itself.
+    set_tx_gas_limit(&master_account, 21_000).assert_success();
+    assert!(!call_as(&alice, echo, Vec::new(), 0).is_ok());
+
+    set_tx_gas_limit(&master_account, 100_000).assert_success();
+    call_as(&alice, echo, Vec::new(), 0).assert_success();
+}
+
+#[test]
+fn test_block_tx_limit() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&alice, &hex::decode(CALLER_ECHO).unwrap());
+
+    let set_block_tx_limit = |limit: u64| {
+        master_account
+            .call(
+                contract_id(),
+                "set_block_tx_limit",
+                &limit.try_to_vec().unwrap(),
+                DEFAULT_GAS,
+                0,
+            )
+            .assert_success();
+    };
+    set_block_tx_limit(0);
+    assert!(!call_as(&alice, echo, Vec::new(), 0).is_ok());
+
+    set_block_tx_limit(10);
+    call_as(&alice, echo, Vec::new(), 0).assert_success();
+}
@@ -0,0 +1,98 @@
+use near_sdk::borsh::BorshDeserialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::precompiles::NEAR_BLOCK_INFO_FLAG;
+use aurora_engine::prelude::Address;
+use aurora_engine::types::keccak;
+
+mod common;
+
+use common::{call_as, contract_id, deploy_code, deploy_evm};
+
+/// Init code for a contract with empty runtime code.
+const EMPTY_CONTRACT: &str = "60006000f3";
+
+/// Init code for a contract that calls `target` and returns whether the call
+/// succeeded as a word, followed by the data the call returned.
+fn call_and_return(target: Address) -> Vec<u8> {
+    let mut code = hex::decode("6031600c60003960316000f3").unwrap();
+    code.extend_from_slice(&hex::decode("60006000600060006000").unwrap());
+    code.push(0x73);
+    code.extend_from_slice(target.as_bytes());
+    code.extend_from_slice(&hex::decode("5af16000523d600060203e3d6020016000f3").unwrap());
+    code
+}
+
+fn get_paused_methods(account: &UserAccount) -> Vec<String> {
+    let result = account
+        .view(contract_id(), "get_paused_methods", &[])
+        .unwrap();
+    Vec::<String>::try_from_slice(&result).unwrap()
+}
+
+#[test]
+fn test_emergency_account_pauses_methods() {
+    let (master_account, _contract_account) = deploy_evm();
+    let guard = master_account.create_user("guard.root".to_string(), to_yocto("100"));
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+
+    let call = |account: &UserAccount, method: &str, input: &[u8]| {
+        account.call(contract_id(), method, input, DEFAULT_GAS, 0)
+    };
+
+    assert!(!call(&guard, "pause_method", b"deploy_code").is_ok());
+    call(&master_account, "set_emergency_account", b"guard.root").assert_success();
+    call(&guard, "pause_method", b"deploy_code").assert_success();
+    assert_eq!(get_paused_methods(&alice), vec!["deploy_code".to_string()]);
+
+    let code = hex::decode(EMPTY_CONTRACT).unwrap();
+    assert!(!call(&alice, "deploy_code", &code).is_ok());
+
+    // The emergency account cannot resume methods, or do anything else the
+    // owner can.
+    assert!(!call(&guard, "resume_method", b"deploy_code").is_ok());
+    assert!(!call(&guard, "set_emergency_account", b"alice.root").is_ok());
+    assert!(!call(&guard, "pause_method", b"get_balance").is_ok());
+
+    call(&master_account, "resume_method", b"deploy_code").assert_success();
+    assert!(get_paused_methods(&alice).is_empty());
+    deploy_code(&alice, &code);
+}
+
+#[test]
+fn test_emergency_account_pauses_precompiles() {
+    let (master_account, _contract_account) = deploy_evm();
+    let guard = master_account.create_user("guard.root".to_string(), to_yocto("100"));
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let near_block_info = Address::from_slice(&keccak(b"nearBlockInfo")[12..]);
+    let mask = NEAR_BLOCK_INFO_FLAG.to_le_bytes();
+
+    let call = |account: &UserAccount, method: &str, input: &[u8]| {
+        account.call(contract_id(), method, input, DEFAULT_GAS, 0)
+    };
+    let get_paused_precompiles = || {
+        let result = alice
+            .view(contract_id(), "get_paused_precompiles", &[])
+            .unwrap();
+        u32::try_from_slice(&result).unwrap()
+    };
+
+    call_as(&alice, near_block_info, Vec::new(), 0).assert_success();
+
+    call(&master_account, "set_emergency_account", b"guard.root").assert_success();
+    call(&guard, "pause_precompiles", &mask).assert_success();
+    assert_eq!(get_paused_precompiles(), NEAR_BLOCK_INFO_FLAG);
+    assert!(!call_as(&alice, near_block_info, Vec::new(), 0).is_ok());
+
+    // Contracts calling a paused precompile see the call fail, without
+    // return data.
+    let caller = deploy_code(&alice, &call_and_return(near_block_info));
+    let result = call_as(&alice, caller, Vec::new(), 0);
+    result.assert_success();
+    assert_eq!(result.unwrap(), vec![0u8; 32]);
+
+    assert!(!call(&guard, "resume_precompiles", &mask).is_ok());
+    call(&master_account, "resume_precompiles", &mask).assert_success();
+    assert_eq!(get_paused_precompiles(), 0);
+    call_as(&alice, near_block_info, Vec::new(), 0).assert_success();
+}
@@ -0,0 +1,59 @@
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::GasPriceBounds;
+use aurora_engine::prelude::U256;
+use aurora_engine::types::u256_to_arr;
+
+mod common;
+
+use common::{contract_id, deploy_evm};
+
+fn bounds(min: u64, max: u64) -> GasPriceBounds {
+    GasPriceBounds {
+        min: u256_to_arr(&U256::from(min)),
+        max: u256_to_arr(&U256::from(max)),
+    }
+}
+
+fn get_bounds(account: &UserAccount) -> (U256, U256) {
+    let result = account
+        .view(contract_id(), "get_gas_price_bounds", &[])
+        .unwrap();
+    let bounds = GasPriceBounds::try_from_slice(&result).unwrap();
+    (
+        U256::from_big_endian(&bounds.min),
+        U256::from_big_endian(&bounds.max),
+    )
+}
+
+#[test]
+fn test_set_gas_price_bounds() {
+    let (master_account, _contract_account) = deploy_evm();
+    assert_eq!(
+        get_bounds(&master_account),
+        (U256::zero(), U256::max_value())
+    );
+
+    let set_bounds = |bounds: GasPriceBounds| {
+        master_account.call(
+            contract_id(),
+            "set_gas_price_bounds",
+            &bounds.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+    };
+    set_bounds(bounds(1, 1_000)).assert_success();
+    assert_eq!(
+        get_bounds(&master_account),
+        (U256::from(1), U256::from(1_000))
+    );
+
+    // The minimum cannot be above the maximum.
+    assert!(!set_bounds(bounds(1_000, 1)).is_ok());
+    assert_eq!(
+        get_bounds(&master_account),
+        (U256::from(1), U256::from(1_000))
+    );
+}
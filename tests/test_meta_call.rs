@@ -0,0 +1,74 @@
+use near_crypto::{InMemorySigner, KeyType};
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::prelude::{Address, U256};
+
+mod common;
+
+use common::{contract_id, deploy_evm, encode_meta_call_function_args, public_key_to_address};
+
+/// Chain id `deploy_evm` initializes the engine with.
+const CHAIN_ID: u64 = 0;
+
+fn get_nonce(account: &UserAccount, address: Address) -> U256 {
+    let result = account
+        .view(contract_id(), "get_nonce", address.as_bytes())
+        .unwrap();
+    U256::from_big_endian(&result)
+}
+
+fn sign_meta_call(signer: &InMemorySigner, chain_id: u64, nonce: U256) -> Vec<u8> {
+    encode_meta_call_function_args(
+        signer,
+        chain_id,
+        &contract_id(),
+        nonce,
+        U256::zero(),
+        Address::zero(),
+        Address::from_low_u64_be(0x1234),
+        U256::zero(),
+        "adopt(uint256 petId)",
+        // RLP encode of ["0x09"]
+        hex::decode("c109").unwrap(),
+    )
+}
+
+#[test]
+fn test_meta_call_relayed() {
+    let (master_account, _contract_account) = deploy_evm();
+    let relayer = master_account.create_user("relayer".to_string(), to_yocto("100"));
+    let signer = InMemorySigner::from_seed("doesnt", KeyType::SECP256K1, "a");
+    let sender = public_key_to_address(signer.public_key.clone());
+    assert_eq!(get_nonce(&relayer, sender), U256::zero());
+
+    let meta_tx = sign_meta_call(&signer, CHAIN_ID, U256::zero());
+    relayer
+        .call(contract_id(), "meta_call", &meta_tx, DEFAULT_GAS, 0)
+        .assert_success();
+    assert_eq!(get_nonce(&relayer, sender), U256::one());
+
+    // The same signed meta-call cannot be relayed twice.
+    let result = relayer.call(contract_id(), "meta_call", &meta_tx, DEFAULT_GAS, 0);
+    assert!(!result.is_ok());
+    assert_eq!(get_nonce(&relayer, sender), U256::one());
+
+    let meta_tx = sign_meta_call(&signer, CHAIN_ID, U256::one());
+    relayer
+        .call(contract_id(), "meta_call", &meta_tx, DEFAULT_GAS, 0)
+        .assert_success();
+    assert_eq!(get_nonce(&relayer, sender), U256::from(2));
+}
+
+#[test]
+fn test_meta_call_wrong_chain_id() {
+    let (master_account, _contract_account) = deploy_evm();
+    let relayer = master_account.create_user("relayer".to_string(), to_yocto("100"));
+    let signer = InMemorySigner::from_seed("doesnt", KeyType::SECP256K1, "a");
+    let sender = public_key_to_address(signer.public_key.clone());
+
+    // Signed for another chain, so the signature recovers to some other
+    // address and the signer's own nonce is left untouched.
+    let meta_tx = sign_meta_call(&signer, 1313161555, U256::zero());
+    relayer.call(contract_id(), "meta_call", &meta_tx, DEFAULT_GAS, 0);
+    assert_eq!(get_nonce(&relayer, sender), U256::zero());
+}
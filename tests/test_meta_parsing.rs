@@ -1,77 +1,11 @@
-use borsh::BorshSerialize;
+use near_crypto::{InMemorySigner, KeyType};
 
-use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
-
-use aurora_engine::meta_parsing::{near_erc712_domain, parse_meta_call, prepare_meta_call_args};
-use aurora_engine::parameters::MetaCallArgs;
+use aurora_engine::meta_parsing::{near_erc712_domain, parse_meta_call};
 use aurora_engine::prelude::{Address, U256};
-use aurora_engine::types::{keccak, u256_to_arr, InternalMetaCallArgs};
 
-pub fn encode_meta_call_function_args(
-    signer: &dyn Signer,
-    chain_id: u64,
-    nonce: U256,
-    fee_amount: U256,
-    fee_address: Address,
-    contract_address: Address,
-    value: U256,
-    method_def: &str,
-    args: Vec<u8>,
-) -> Vec<u8> {
-    let domain_separator = near_erc712_domain(U256::from(chain_id));
-    let (msg, _) = match prepare_meta_call_args(
-        &domain_separator,
-        "evm".as_bytes(),
-        method_def.to_string(),
-        &InternalMetaCallArgs {
-            sender: Address::zero(),
-            nonce,
-            fee_amount,
-            fee_address,
-            contract_address,
-            value,
-            input: args.clone(),
-        },
-    ) {
-        Ok(x) => x,
-        Err(_) => panic!("Failed to prepare"),
-    };
-    match signer.sign(&msg) {
-        Signature::ED25519(_) => panic!("Wrong Signer"),
-        Signature::SECP256K1(sig) => {
-            let array = Into::<[u8; 65]>::into(sig.clone()).to_vec();
-            let mut signature = [0u8; 64];
-            signature.copy_from_slice(&array[..64]);
-            MetaCallArgs {
-                signature,
-                // Add 27 to align eth-sig-util signature format
-                v: 27,
-                nonce: u256_to_arr(&nonce),
-                fee_amount: u256_to_arr(&fee_amount),
-                fee_address: fee_address.0,
-                contract_address: contract_address.0,
-                value: u256_to_arr(&value),
-                method_def: method_def.to_string(),
-                args,
-            }
-            .try_to_vec()
-            .expect("Failed to serialize")
-        }
-    }
-}
+mod common;
 
-pub fn public_key_to_address(public_key: PublicKey) -> Address {
-    match public_key {
-        PublicKey::ED25519(_) => panic!("Wrong PublicKey"),
-        PublicKey::SECP256K1(pubkey) => {
-            let pk: [u8; 64] = pubkey.into();
-            let bytes = keccak(&pk.to_vec());
-            let mut result = Address::zero();
-            result.as_bytes_mut().copy_from_slice(&bytes[12..]);
-            result
-        }
-    }
-}
+use common::{encode_meta_call_function_args, public_key_to_address};
 
 #[test]
 fn test_meta_parsing() {
@@ -83,6 +17,7 @@ fn test_meta_parsing() {
     let meta_tx = encode_meta_call_function_args(
         &signer,
         chain_id,
+        "evm",
         U256::from(14),
         U256::from(6),
         Address::from_slice(&[0u8; 20]),
@@ -103,6 +38,7 @@ fn test_meta_parsing() {
     let meta_tx3 = encode_meta_call_function_args(
         &signer,
         chain_id,
+        "evm",
         U256::from(14),
         U256::from(6),
         Address::from_slice(&[0u8; 20]),
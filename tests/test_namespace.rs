@@ -0,0 +1,57 @@
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::prelude::Address;
+
+mod common;
+
+use common::{contract_id, deploy_code, deploy_evm};
+
+/// Runtime code `CALLER PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN`, deployed by
+/// the init code of `test_contract_call`.
+const CALLER_ECHO: &str = "6009600c60003960096000f33360005260206000f3";
+
+fn set_namespace(account: &UserAccount, namespace: &[u8]) -> ExecutionResult {
+    account.call(
+        contract_id(),
+        "set_storage_namespace",
+        namespace,
+        DEFAULT_GAS,
+        0,
+    )
+}
+
+fn get_code(account: &UserAccount, address: Address) -> Vec<u8> {
+    account
+        .view(contract_id(), "get_code", address.as_bytes())
+        .unwrap()
+}
+
+#[test]
+fn test_storage_namespaces() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let echo = deploy_code(&master_account, &hex::decode(CALLER_ECHO).unwrap());
+    assert!(!get_code(&master_account, echo).is_empty());
+
+    assert!(!set_namespace(&alice, b"silo1").is_ok());
+    set_namespace(&master_account, b"silo1").assert_success();
+    let namespace = master_account
+        .view(contract_id(), "get_storage_namespace", &[])
+        .unwrap();
+    assert_eq!(namespace, b"silo1".to_vec());
+
+    // A new namespace starts without accounts, but with the same owner.
+    assert!(get_code(&master_account, echo).is_empty());
+    assert!(!set_namespace(&alice, b"").is_ok());
+    let silo_echo = deploy_code(&master_account, &hex::decode(CALLER_ECHO).unwrap());
+    assert!(!get_code(&master_account, silo_echo).is_empty());
+
+    set_namespace(&master_account, b"").assert_success();
+    assert!(!get_code(&master_account, echo).is_empty());
+
+    // Namespaces are length-prefixed, so "silo" and "silo1" do not overlap.
+    set_namespace(&master_account, b"silo").assert_success();
+    assert!(get_code(&master_account, silo_echo).is_empty());
+
+    assert!(!set_namespace(&master_account, &[b'a'; 256]).is_ok());
+}
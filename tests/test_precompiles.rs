@@ -0,0 +1,113 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::prelude::Address;
+use aurora_engine::types::{keccak, RawAddress};
+
+mod common;
+
+use common::{call_as, contract_id, deploy_code, deploy_evm};
+
+/// Address of the `nearBlockInfo` precompile.
+fn near_block_info() -> Address {
+    Address::from_slice(&keccak(b"nearBlockInfo")[12..])
+}
+
+fn word(output: &[u8], index: usize) -> u64 {
+    let word = &output[index * 32..(index + 1) * 32];
+    assert_eq!(&word[..24], &[0u8; 24][..]);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&word[24..]);
+    u64::from_be_bytes(bytes)
+}
+
+#[test]
+fn test_near_block_info() {
+    let (master_account, _contract_account) = deploy_evm();
+
+    let result = call_as(&master_account, near_block_info(), Vec::new(), 0);
+    result.assert_success();
+    let first = result.unwrap();
+    assert_eq!(first.len(), 96);
+
+    let result = call_as(&master_account, near_block_info(), Vec::new(), 0);
+    result.assert_success();
+    let second = result.unwrap();
+    assert_eq!(second.len(), 96);
+
+    assert!(word(&second, 0) > word(&first, 0));
+    assert!(word(&second, 1) >= word(&first, 1));
+    assert!(word(&second, 2) >= word(&first, 2));
+}
+
+fn set_disabled_precompiles(account: &UserAccount, addresses: Vec<RawAddress>) -> bool {
+    account
+        .call(
+            contract_id(),
+            "set_disabled_precompiles",
+            &addresses.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+        .is_ok()
+}
+
+#[test]
+fn test_disabled_precompiles() {
+    let (master_account, _contract_account) = deploy_evm();
+    let identity = Address::from_low_u64_be(4);
+
+    let result = call_as(&master_account, identity, vec![1, 2, 3], 0);
+    assert_eq!(result.unwrap(), vec![1, 2, 3]);
+
+    // Disabled precompiles behave like accounts without code.
+    assert!(set_disabled_precompiles(
+        &master_account,
+        vec![identity.0, near_block_info().0]
+    ));
+    let result = call_as(&master_account, identity, vec![1, 2, 3], 0);
+    assert_eq!(result.unwrap(), Vec::<u8>::new());
+    let result = call_as(&master_account, near_block_info(), Vec::new(), 0);
+    assert_eq!(result.unwrap(), Vec::<u8>::new());
+
+    assert!(set_disabled_precompiles(&master_account, Vec::new()));
+    let result = call_as(&master_account, identity, vec![1, 2, 3], 0);
+    assert_eq!(result.unwrap(), vec![1, 2, 3]);
+
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    assert!(!set_disabled_precompiles(&alice, vec![identity.0]));
+}
+
+/// Init code for a contract that `STATICCALL`s modexp with the gas in the
+/// first word of its input and the rest of its input, and returns whether
+/// the call succeeded.
+const MODEXP_CALLER: &str = "6021600c60003960216000f3\
+    602036036020600037\
+    600060006020360360006005600035fa\
+    60005260206000f3";
+
+fn modexp_succeeds(account: &UserAccount, caller: Address, gas: u8) -> bool {
+    // 3 ** 5 % 7, with one-byte operands.
+    let mut input = vec![0u8; 32];
+    input[31] = gas;
+    for _ in 0..3 {
+        let mut length = [0u8; 32];
+        length[31] = 1;
+        input.extend_from_slice(&length);
+    }
+    input.extend_from_slice(&[3, 5, 7]);
+
+    let result = call_as(account, caller, input, 0);
+    result.assert_success();
+    result.unwrap()[31] == 1
+}
+
+#[test]
+fn test_modexp_eip2565_gas() {
+    let (master_account, _contract_account) = deploy_evm();
+    let caller = deploy_code(&master_account, &hex::decode(MODEXP_CALLER).unwrap());
+
+    // EIP-198 prices this at 0 gas, EIP-2565 at its minimum of 200.
+    assert!(!modexp_succeeds(&master_account, caller, 199));
+    assert!(modexp_succeeds(&master_account, caller, 200));
+}
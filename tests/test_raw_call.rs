@@ -0,0 +1,110 @@
+use near_crypto::{InMemorySigner, KeyType};
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::BlockCheckpoint;
+use aurora_engine::prelude::{Address, U256};
+use aurora_engine::transaction::EthTransaction;
+
+mod common;
+
+use common::{contract_id, deploy_evm, public_key_to_address, sign_transaction};
+
+/// Intrinsic gas of a transaction.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// A signed transfer of nothing from the signer's address to `0x1234`.
+fn sign_transfer(signer: &InMemorySigner, nonce: u64) -> Vec<u8> {
+    sign_transaction(
+        signer,
+        EthTransaction {
+            nonce: U256::from(nonce),
+            gas_price: U256::zero(),
+            gas: U256::from(TRANSFER_GAS),
+            to: Some(Address::from_low_u64_be(0x1234)),
+            value: U256::zero(),
+            data: Vec::new(),
+        },
+    )
+}
+
+fn raw_call(account: &UserAccount, input: &[u8]) -> ExecutionResult {
+    account.call(contract_id(), "raw_call", input, DEFAULT_GAS, 0)
+}
+
+fn get_nonce(account: &UserAccount, address: Address) -> U256 {
+    let result = account
+        .view(contract_id(), "get_nonce", address.as_bytes())
+        .unwrap();
+    U256::from_big_endian(&result)
+}
+
+fn current_height(account: &UserAccount) -> u64 {
+    account.borrow_runtime().current_block().block_height
+}
+
+/// Sums the checkpoints of the blocks from `start` to `end`.
+fn transactions_between(account: &UserAccount, start: u64, end: u64) -> (u64, u64) {
+    (start..=end)
+        .map(|height| {
+            let result = account
+                .view(
+                    contract_id(),
+                    "get_block_checkpoint",
+                    &height.try_to_vec().unwrap(),
+                )
+                .unwrap();
+            BlockCheckpoint::try_from_slice(&result).unwrap()
+        })
+        .fold((0, 0), |(count, gas), checkpoint| {
+            (
+                count + checkpoint.transaction_count,
+                gas + checkpoint.gas_used,
+            )
+        })
+}
+
+#[test]
+fn test_raw_transfer_recorded_in_checkpoint() {
+    let (master_account, _contract_account) = deploy_evm();
+    let relayer = master_account.create_user("relayer".to_string(), to_yocto("100"));
+    let signer = InMemorySigner::from_seed("doesnt", KeyType::SECP256K1, "a");
+    let sender = public_key_to_address(signer.public_key.clone());
+
+    let start = current_height(&relayer);
+    raw_call(&relayer, &sign_transfer(&signer, 0)).assert_success();
+    let end = current_height(&relayer);
+
+    assert_eq!(get_nonce(&relayer, sender), U256::one());
+    assert_eq!(
+        transactions_between(&relayer, start, end),
+        (1, TRANSFER_GAS)
+    );
+}
+
+#[test]
+fn test_raw_transfer_block_tx_limit() {
+    let (master_account, _contract_account) = deploy_evm();
+    let relayer = master_account.create_user("relayer".to_string(), to_yocto("100"));
+    let signer = InMemorySigner::from_seed("doesnt", KeyType::SECP256K1, "a");
+    let sender = public_key_to_address(signer.public_key.clone());
+
+    let set_block_tx_limit = |limit: u64| {
+        master_account
+            .call(
+                contract_id(),
+                "set_block_tx_limit",
+                &limit.try_to_vec().unwrap(),
+                DEFAULT_GAS,
+                0,
+            )
+            .assert_success();
+    };
+    set_block_tx_limit(0);
+    assert!(!raw_call(&relayer, &sign_transfer(&signer, 0)).is_ok());
+    assert_eq!(get_nonce(&relayer, sender), U256::zero());
+
+    set_block_tx_limit(10);
+    raw_call(&relayer, &sign_transfer(&signer, 0)).assert_success();
+    assert_eq!(get_nonce(&relayer, sender), U256::one());
+}
@@ -0,0 +1,47 @@
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS};
+
+use aurora_engine::parameters::AllowRelayerArgs;
+
+mod common;
+
+use common::{contract_id, deploy_evm};
+
+fn get_relayer_quota(account: &UserAccount, relayer: &str) -> Option<u64> {
+    let result = account
+        .view(contract_id(), "get_relayer_quota", relayer.as_bytes())
+        .unwrap();
+    Option::<u64>::try_from_slice(&result).unwrap()
+}
+
+#[test]
+fn test_allow_relayer() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let args = AllowRelayerArgs {
+        account_id: "relayer.root".to_string(),
+        transactions_per_block: 3,
+    }
+    .try_to_vec()
+    .unwrap();
+
+    let result = alice.call(contract_id(), "allow_relayer", &args, DEFAULT_GAS, 0);
+    assert!(!result.is_ok());
+    assert_eq!(get_relayer_quota(&alice, "relayer.root"), None);
+
+    master_account
+        .call(contract_id(), "allow_relayer", &args, DEFAULT_GAS, 0)
+        .assert_success();
+    assert_eq!(get_relayer_quota(&alice, "relayer.root"), Some(3));
+
+    master_account
+        .call(
+            contract_id(),
+            "disallow_relayer",
+            b"relayer.root",
+            DEFAULT_GAS,
+            0,
+        )
+        .assert_success();
+    assert_eq!(get_relayer_quota(&alice, "relayer.root"), None);
+}
@@ -0,0 +1,68 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk_sim::UserAccount;
+
+use aurora_engine::parameters::GetStorageAtArgs;
+use aurora_engine::prelude::Address;
+
+mod common;
+
+use common::{
+    call_as, contract_id, deploy_code, deploy_evm, raw_storage_slots, storage_generation,
+};
+
+/// Init code of a factory that, when called, creates the child whose init
+/// code ends its own code with `CREATE2` and salt 0, so always at the same
+/// address, and returns the child's address.
+const FACTORY: &str = "6031600c60003960316000f3\
+    601960186000396000601960006000f560005260206000f3\
+    600d600c600039600d6000f33660065733ff5b602a60005500";
+
+fn create_child(account: &UserAccount, factory: Address) -> Address {
+    let result = call_as(account, factory, Vec::new(), 0);
+    result.assert_success();
+    let word = result.unwrap();
+    Address::from_slice(&word[12..])
+}
+
+fn slot_zero(account: &UserAccount, address: Address) -> Vec<u8> {
+    let args = GetStorageAtArgs {
+        address: address.0,
+        key: [0u8; 32],
+    };
+    account
+        .view(contract_id(), "get_storage_at", &args.try_to_vec().unwrap())
+        .unwrap()
+}
+
+#[test]
+fn test_recreated_contract_sees_empty_storage() {
+    let (master_account, _contract_account) = deploy_evm();
+    let factory = deploy_code(&master_account, &hex::decode(FACTORY).unwrap());
+
+    // The child stores 42 in slot 0 when called with data, and
+    // self-destructs when called without.
+    let child = create_child(&master_account, factory);
+    assert_ne!(child, Address::zero());
+    call_as(&master_account, child, vec![1], 0).assert_success();
+    assert_eq!(slot_zero(&master_account, child)[31], 42);
+    let generation = storage_generation(&master_account, child);
+    assert_eq!(
+        raw_storage_slots(&master_account, child),
+        vec![(generation, [0u8; 32], vec![42])]
+    );
+
+    call_as(&master_account, child, Vec::new(), 0).assert_success();
+    assert!(storage_generation(&master_account, child) > generation);
+
+    assert_eq!(create_child(&master_account, factory), child);
+    let recreated_generation = storage_generation(&master_account, child);
+    assert!(recreated_generation > generation);
+    assert_eq!(slot_zero(&master_account, child), vec![0u8; 32]);
+
+    // The old slot is still stored, but in the old generation only.
+    let slots = raw_storage_slots(&master_account, child);
+    assert_eq!(slots, vec![(generation, [0u8; 32], vec![42])]);
+    assert!(slots
+        .iter()
+        .all(|(slot_generation, _, _)| *slot_generation < recreated_generation));
+}
@@ -0,0 +1,220 @@
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+
+use aurora_engine::parameters::{
+    ExportStateArgs, GetStorageAtArgs, HashStateArgs, NewCallArgs, StateChunk, StateHash,
+};
+use aurora_engine::prelude::Address;
+use aurora_engine::types::keccak;
+
+mod common;
+
+use common::{contract_id, deploy_code, deploy_evm, EVM_WASM_BYTES};
+
+/// Init code that stores 42 in slot 0, then deploys the `CALLER` echo
+/// contract of `test_contract_call`.
+const STORE_AND_ECHO: &str = "602a6000556009601160003960096000f33360005260206000f3";
+
+const COPY_ID: &str = "copy.root";
+
+fn deploy_copy(master_account: &UserAccount) {
+    master_account.deploy(*EVM_WASM_BYTES, COPY_ID.to_string(), to_yocto("1000"));
+    let args = NewCallArgs {
+        chain_id: [0u8; 32],
+        owner_id: master_account.account_id.clone(),
+        bridge_prover_id: COPY_ID.to_string(),
+        upgrade_delay_blocks: 1,
+    };
+    master_account
+        .call(
+            COPY_ID.to_string(),
+            "new",
+            &args.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            STORAGE_AMOUNT,
+        )
+        .assert_success();
+}
+
+fn export_chunk(master_account: &UserAccount, from: Vec<u8>, manifest: [u8; 32]) -> StateChunk {
+    let args = ExportStateArgs {
+        from,
+        limit: 2,
+        manifest,
+    };
+    let result = master_account.call(
+        contract_id(),
+        "export_state",
+        &args.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    );
+    result.assert_success();
+    StateChunk::try_from_slice(&result.unwrap()).unwrap()
+}
+
+fn view_state(account: &UserAccount, engine_id: &str, address: Address) -> (Vec<u8>, Vec<u8>) {
+    let code = account
+        .view(engine_id.to_string(), "get_code", address.as_bytes())
+        .unwrap();
+    let args = GetStorageAtArgs {
+        address: address.0,
+        key: [0u8; 32],
+    };
+    let slot = account
+        .view(
+            engine_id.to_string(),
+            "get_storage_at",
+            &args.try_to_vec().unwrap(),
+        )
+        .unwrap();
+    (code, slot)
+}
+
+fn hash_state(account: &UserAccount, exclude: &[Vec<u8>]) -> [u8; 32] {
+    let mut from = Vec::new();
+    let mut manifest = [0u8; 32];
+    loop {
+        let args = HashStateArgs {
+            from,
+            limit: 3,
+            manifest,
+            exclude: exclude.to_vec(),
+        };
+        let result = account
+            .view(contract_id(), "hash_state", &args.try_to_vec().unwrap())
+            .unwrap();
+        let hash = StateHash::try_from_slice(&result).unwrap();
+        manifest = hash.manifest;
+        match hash.next {
+            Some(key) => from = key,
+            None => return manifest,
+        }
+    }
+}
+
+#[test]
+fn test_export_import_state() {
+    let (master_account, _contract_account) = deploy_evm();
+    let contract = deploy_code(&master_account, &hex::decode(STORE_AND_ECHO).unwrap());
+    deploy_copy(&master_account);
+
+    let mut chunks = Vec::new();
+    let mut from = Vec::new();
+    let mut manifest = [0u8; 32];
+    loop {
+        let chunk = export_chunk(&master_account, from, manifest);
+        assert!(chunk.entries.len() <= 2);
+        manifest = chunk.manifest;
+        let next = chunk.next.clone();
+        chunks.push(chunk);
+        match next {
+            Some(key) => from = key,
+            None => break,
+        }
+    }
+    assert!(chunks.len() > 1);
+
+    // Chunks must be imported in export order.
+    let result = master_account.call(
+        COPY_ID.to_string(),
+        "import_state",
+        &chunks[1].try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    );
+    assert!(!result.is_ok());
+
+    for chunk in &chunks {
+        let result = master_account.call(
+            COPY_ID.to_string(),
+            "import_state",
+            &chunk.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        );
+        result.assert_success();
+        assert_eq!(result.unwrap(), chunk.manifest.to_vec());
+    }
+    assert_eq!(manifest, chunks.last().unwrap().manifest);
+
+    let (code, slot) = view_state(&master_account, &contract_id(), contract);
+    assert!(!code.is_empty());
+    assert_eq!(slot[31], 42);
+    assert_eq!(view_state(&master_account, COPY_ID, contract), (code, slot));
+}
+
+/// A first chunk holding just `entries`.
+fn first_chunk(entries: Vec<(Vec<u8>, Vec<u8>)>) -> StateChunk {
+    let mut bytes = [0u8; 64];
+    bytes[32..].copy_from_slice(keccak(&entries.try_to_vec().unwrap()).as_bytes());
+    StateChunk {
+        entries,
+        next: None,
+        manifest: keccak(&bytes).0,
+    }
+}
+
+#[test]
+fn test_import_state_rejects_other_keys() {
+    let (master_account, _contract_account) = deploy_evm();
+    let import = |key: Vec<u8>| {
+        let chunk = first_chunk(vec![(key, vec![1])]);
+        master_account.call(
+            contract_id(),
+            "import_state",
+            &chunk.try_to_vec().unwrap(),
+            DEFAULT_GAS,
+            0,
+        )
+    };
+
+    let mut balance_key = vec![0x2];
+    balance_key.extend_from_slice(&[0x11; 20]);
+    // Configuration, a namespaced key, a prefix past the last one, and an
+    // account prefix with a key of the wrong length.
+    for key in &[
+        b"\0STATE".to_vec(),
+        [&[0xff][..], b"ns", &balance_key].concat(),
+        [&[0xa][..], &[0x11; 20][..]].concat(),
+        balance_key[..20].to_vec(),
+    ] {
+        assert!(!import(key.clone()).is_ok(), "{}", hex::encode(key));
+    }
+
+    import(balance_key).assert_success();
+}
+
+#[test]
+fn test_export_state_owner_only() {
+    let (master_account, _contract_account) = deploy_evm();
+    let alice = master_account.create_user("alice.root".to_string(), to_yocto("100"));
+    let args = ExportStateArgs {
+        from: Vec::new(),
+        limit: 2,
+        manifest: [0u8; 32],
+    };
+    let result = alice.call(
+        contract_id(),
+        "export_state",
+        &args.try_to_vec().unwrap(),
+        DEFAULT_GAS,
+        0,
+    );
+    assert!(!result.is_ok());
+}
+
+#[test]
+fn test_hash_state_excluded_keys() {
+    let (master_account, _contract_account) = deploy_evm();
+    // Every EVM account record, but none of the engine configuration.
+    let accounts: Vec<Vec<u8>> = (0x1..=0x9).map(|prefix| vec![prefix]).collect();
+
+    let all_before = hash_state(&master_account, &[]);
+    let config_before = hash_state(&master_account, &accounts);
+    assert_eq!(hash_state(&master_account, &[]), all_before);
+
+    deploy_code(&master_account, &hex::decode(STORE_AND_ECHO).unwrap());
+    assert_ne!(hash_state(&master_account, &[]), all_before);
+    assert_eq!(hash_state(&master_account, &accounts), config_before);
+}
@@ -1,55 +1,187 @@
 use near_sdk::borsh::BorshSerialize;
-use near_sdk::test_utils::accounts;
-use near_sdk_sim::{to_yocto, UserAccount, DEFAULT_GAS, STORAGE_AMOUNT};
+use near_sdk_sim::{UserAccount, DEFAULT_GAS};
+use serde::Deserialize;
 
-use aurora_engine::parameters::NewCallArgs;
+use aurora_engine::parameters::{GetStorageAtArgs, NewCallArgs, StateChunk};
+use aurora_engine::prelude::{Address, U256};
+use aurora_engine::types::keccak;
 
-near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
-    EVM_WASM_BYTES => "release.wasm"
+mod common;
+
+use common::{
+    call_as, contract_id, deploy_code, deploy_evm, deploy_evm_with_args, deploy_upgrade,
+    stage_upgrade, upgrade_evm, wait_for_upgrade, EVM_DEBUG_WASM_BYTES,
+    EVM_MIGRATION_TEST_WASM_BYTES, EVM_WASM_BYTES,
+};
+
+/// Init code that stores 42 in slot 0, then deploys the `CALLER` echo
+/// contract of `test_contract_call`.
+const STORE_AND_ECHO: &str = "602a6000556009601160003960096000f33360005260206000f3";
+
+fn storage_at(account: &UserAccount, address: Address, slot: u64) -> Vec<u8> {
+    let mut key = [0u8; 32];
+    key[24..].copy_from_slice(&slot.to_be_bytes());
+    let args = GetStorageAtArgs {
+        address: address.0,
+        key,
+    };
+    account
+        .view(contract_id(), "get_storage_at", &args.try_to_vec().unwrap())
+        .unwrap()
+}
+
+fn slot_zero(account: &UserAccount, address: Address) -> Vec<u8> {
+    storage_at(account, address, 0)
+}
+
+fn view_u256(account: &UserAccount, method: &str, address: Address) -> U256 {
+    let result = account
+        .view(contract_id(), method, address.as_bytes())
+        .unwrap();
+    U256::from_big_endian(&result)
+}
+
+fn state_version(account: &UserAccount) -> u64 {
+    let result = account
+        .view(contract_id(), "get_state_version", &[])
+        .unwrap();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&result);
+    u64::from_le_bytes(bytes)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DumpEntry {
+    key: String,
+    value: String,
 }
 
-fn init() -> (UserAccount, UserAccount) {
-    let master_account = near_sdk_sim::init_simulator(None);
-    let contract_account =
-        master_account.deploy(*EVM_WASM_BYTES, accounts(0).to_string(), to_yocto("1000"));
-    contract_account
+/// Imports a raw state dump from `tests/data` in a single chunk.
+fn import_dump(owner: &UserAccount, file: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(file);
+    let json = std::fs::read_to_string(path).unwrap();
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = serde_json::from_str::<Vec<DumpEntry>>(&json)
+        .unwrap()
+        .into_iter()
+        .map(|entry| {
+            (
+                hex::decode(entry.key).unwrap(),
+                hex::decode(entry.value).unwrap(),
+            )
+        })
+        .collect();
+
+    // The first chunk's manifest chains onto zeros, see `state_export`.
+    let mut bytes = [0u8; 64];
+    bytes[32..].copy_from_slice(keccak(&entries.try_to_vec().unwrap()).as_bytes());
+    let chunk = StateChunk {
+        entries,
+        next: None,
+        manifest: keccak(&bytes).0,
+    };
+    owner
         .call(
-            accounts(0).to_string(),
-            "new",
-            &NewCallArgs {
-                chain_id: [0u8; 32],
-                owner_id: master_account.account_id.clone(),
-                bridge_prover_id: accounts(0).to_string(),
-                upgrade_delay_blocks: 1,
-            }
-            .try_to_vec()
-            .unwrap(),
+            contract_id(),
+            "import_state",
+            &chunk.try_to_vec().unwrap(),
             DEFAULT_GAS,
-            STORAGE_AMOUNT,
+            0,
         )
         .assert_success();
-    (master_account, contract_account)
 }
 
 #[test]
 fn test_contract_upgrade() {
-    let (master_account, _contract_account) = init();
-    master_account
-        .call(
-            accounts(0).to_string(),
-            "stage_upgrade",
-            &EVM_WASM_BYTES,
-            DEFAULT_GAS,
-            0,
-        )
-        .assert_success();
-    master_account
-        .call(
-            accounts(0).to_string(),
-            "deploy_upgrade",
-            &[],
-            DEFAULT_GAS,
-            0,
-        )
-        .assert_success();
+    let (master_account, _contract_account) = deploy_evm();
+    upgrade_evm(&master_account, &EVM_WASM_BYTES);
+}
+
+#[test]
+fn test_upgrade_keeps_state() {
+    let (master_account, _contract_account) = deploy_evm();
+    let address = deploy_code(&master_account, &hex::decode(STORE_AND_ECHO).unwrap());
+    assert_eq!(slot_zero(&master_account, address)[31], 42);
+
+    upgrade_evm(&master_account, &EVM_DEBUG_WASM_BYTES);
+    assert_eq!(slot_zero(&master_account, address)[31], 42);
+    let result = call_as(&master_account, address, Vec::new(), 0);
+    result.assert_success();
+    assert_eq!(result.unwrap().len(), 32);
+
+    // And back again.
+    upgrade_evm(&master_account, &EVM_WASM_BYTES);
+    assert_eq!(slot_zero(&master_account, address)[31], 42);
+    call_as(&master_account, address, Vec::new(), 0).assert_success();
+}
+
+#[test]
+fn test_upgrade_delay() {
+    let (master_account, _contract_account) = deploy_evm_with_args(|master_account| NewCallArgs {
+        chain_id: [0u8; 32],
+        owner_id: master_account.account_id.clone(),
+        bridge_prover_id: contract_id(),
+        upgrade_delay_blocks: 1_000,
+    });
+    stage_upgrade(&master_account, &EVM_DEBUG_WASM_BYTES).assert_success();
+    assert!(!deploy_upgrade(&master_account).is_ok());
+
+    wait_for_upgrade(&master_account);
+    deploy_upgrade(&master_account).assert_success();
+}
+
+/// `state_v0.json` is state in the layout of the engine before the state was
+/// versioned: values are not trimmed, code is stored by address and storage
+/// keys have no generation.
+#[test]
+fn test_upgrade_migrates_old_state() {
+    let (master_account, _contract_account) = deploy_evm();
+    import_dump(&master_account, "state_v0.json");
+    assert_eq!(state_version(&master_account), 0);
+
+    upgrade_evm(&master_account, &EVM_MIGRATION_TEST_WASM_BYTES);
+    assert_eq!(state_version(&master_account), 1);
+
+    let contract = Address::repeat_byte(0x11);
+    let account = Address::repeat_byte(0x22);
+    assert_eq!(
+        view_u256(&master_account, "get_nonce", contract),
+        U256::one()
+    );
+    assert_eq!(
+        view_u256(&master_account, "get_nonce", account),
+        U256::from(5)
+    );
+    assert_eq!(
+        view_u256(&master_account, "get_balance", contract),
+        U256::from(1_000)
+    );
+    assert_eq!(
+        view_u256(&master_account, "get_balance", account),
+        U256::exp10(18)
+    );
+    assert_eq!(storage_at(&master_account, contract, 0)[31], 42);
+    assert_eq!(storage_at(&master_account, contract, 7)[0], 0x80);
+
+    // The contract still runs: it echoes the caller.
+    let result = call_as(&master_account, contract, Vec::new(), 0);
+    result.assert_success();
+    assert_eq!(result.unwrap().len(), 32);
+}
+
+#[test]
+fn test_failed_migration_can_be_retried() {
+    let (master_account, _contract_account) = deploy_evm();
+    stage_upgrade(&master_account, &EVM_MIGRATION_TEST_WASM_BYTES).assert_success();
+    wait_for_upgrade(&master_account);
+
+    // Not enough gas left for the migration, so the deployment is reverted.
+    master_account.call(contract_id(), "deploy_upgrade", &[], 100_000_000_000_000, 0);
+    assert_eq!(state_version(&master_account), 0);
+
+    // The staged code is still there.
+    deploy_upgrade(&master_account).assert_success();
+    assert_eq!(state_version(&master_account), 1);
 }